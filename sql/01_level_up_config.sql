@@ -0,0 +1,11 @@
+-- Per-server level-up announcement settings. Servers without a row announce in the channel they leveled up in.
+-- mode is an AnnounceMode: 0 channel, 1 dedicated channel, 2 direct message, 3 disabled.
+
+CREATE TABLE [Ranking].[LevelUpConfig] (
+    id DECIMAL(20, 0) NOT NULL PRIMARY KEY,
+    mode TINYINT NOT NULL DEFAULT 0,
+    channel DECIMAL(20, 0) NULL,
+    template NVARCHAR(1024) NULL,
+    milestone_interval INT NOT NULL DEFAULT 1,
+    rank_changes_only BIT NOT NULL DEFAULT 0
+);
@@ -0,0 +1,4 @@
+-- Whether members keep every rank role they earn (1) or only their highest one (0, the old behaviour).
+
+ALTER TABLE [Ranking].[Server]
+    ADD stack_roles BIT NOT NULL DEFAULT 0;
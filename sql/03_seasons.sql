@@ -0,0 +1,28 @@
+-- Experience seasons: when one closes, everyone's standing is archived and their progress cut down to keep_percent.
+
+CREATE TABLE [Ranking].[SeasonConfig] (
+    id DECIMAL(20, 0) NOT NULL PRIMARY KEY,
+    keep_percent INT NOT NULL DEFAULT 0,
+    interval_days INT NULL,
+    next_close DATETIME2 NULL,
+    champion_role DECIMAL(20, 0) NULL
+);
+
+CREATE TABLE [Ranking].[Season] (
+    server_id DECIMAL(20, 0) NOT NULL,
+    season INT NOT NULL,
+    closed_at DATETIME2 NOT NULL,
+    CONSTRAINT [PK_Ranking_Season] PRIMARY KEY (server_id, season)
+);
+
+CREATE TABLE [Ranking].[SeasonLevel] (
+    server_id DECIMAL(20, 0) NOT NULL,
+    season INT NOT NULL,
+    [user_id] DECIMAL(20, 0) NOT NULL,
+    level INT NOT NULL,
+    xp INT NOT NULL,
+    position BIGINT NOT NULL,
+    CONSTRAINT [PK_Ranking_SeasonLevel] PRIMARY KEY (server_id, season, [user_id])
+);
+
+CREATE INDEX [IX_Ranking_SeasonLevel_Position] ON [Ranking].[SeasonLevel] (server_id, season, position);
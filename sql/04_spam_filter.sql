@@ -0,0 +1,21 @@
+-- Spam filter settings for chat experience, and how often each member has been caught by it.
+-- reason is a SpamReason: 0 too short, 1 duplicate, 2 emoji-only, 3 sticker-only, 4 burst.
+
+CREATE TABLE [Ranking].[SpamFilter] (
+    id DECIMAL(20, 0) NOT NULL PRIMARY KEY,
+    enabled BIT NOT NULL DEFAULT 0,
+    min_length INT NOT NULL DEFAULT 3,
+    duplicate_threshold INT NOT NULL DEFAULT 90,
+    block_emoji_only BIT NOT NULL DEFAULT 1,
+    block_sticker_only BIT NOT NULL DEFAULT 1,
+    burst_limit INT NOT NULL DEFAULT 5,
+    burst_window INT NOT NULL DEFAULT 10000
+);
+
+CREATE TABLE [Ranking].[SpamCounter] (
+    server_id DECIMAL(20, 0) NOT NULL,
+    [user_id] DECIMAL(20, 0) NOT NULL,
+    reason TINYINT NOT NULL,
+    count INT NOT NULL DEFAULT 0,
+    CONSTRAINT [PK_Ranking_SpamCounter] PRIMARY KEY (server_id, [user_id], reason)
+);
@@ -0,0 +1,80 @@
+-- Per-channel and per-role cooldowns for chat experience, in milliseconds like [Ranking].[Server].timeout.
+-- scope is a TimeoutScope: 0 channel, 1 role.
+
+CREATE TABLE [Ranking].[TimeoutOverride] (
+    server_id DECIMAL(20, 0) NOT NULL,
+    scope TINYINT NOT NULL,
+    target_id DECIMAL(20, 0) NOT NULL,
+    timeout INT NOT NULL,
+    CONSTRAINT [PK_Ranking_TimeoutOverride] PRIMARY KEY (server_id, scope, target_id)
+);
+GO
+
+-- The bot works out which override applies to a message and passes it as @timeout; leaving it NULL uses the server's cooldown.
+-- Returns the new level with the old and new rank roles, or -1 as the level when the member didn't level up.
+CREATE OR ALTER PROCEDURE [Ranking].[ProvideExp]
+    @serverid DECIMAL(20, 0),
+    @userid DECIMAL(20, 0),
+    @timeout INT = NULL
+AS
+BEGIN
+    SET NOCOUNT ON;
+    SET XACT_ABORT ON;
+
+    DECLARE @now DATETIME2 = SYSUTCDATETIME();
+    IF @timeout IS NULL
+        SET @timeout = ISNULL((SELECT timeout FROM [Ranking].[Server] WHERE id = @serverid), 0);
+
+    BEGIN TRANSACTION;
+
+    DECLARE @level INT, @xp INT, @last DATETIME2;
+    SELECT @level = level, @xp = xp, @last = last_xp
+    FROM [Ranking].[Level] WITH (UPDLOCK, HOLDLOCK)
+    WHERE server_id = @serverid AND [user_id] = @userid;
+
+    IF @level IS NULL
+    BEGIN
+        SELECT @level = 0, @xp = 0;
+        INSERT INTO [Ranking].[Level] (server_id, [user_id], xp, level) VALUES (@serverid, @userid, 0, 0);
+    END
+    ELSE IF @last IS NOT NULL AND DATEDIFF_BIG(MILLISECOND, @last, @now) < @timeout
+    BEGIN
+        COMMIT TRANSACTION;
+        SELECT -1, CAST(NULL AS DECIMAL(20, 0)), CAST(NULL AS DECIMAL(20, 0));
+        RETURN;
+    END
+
+    DECLARE @old_level INT = @level;
+    SET @xp = @xp + 15 + CAST(RAND() * 11 AS INT);
+
+    DECLARE @step TABLE (xp INT);
+    DECLARE @next INT;
+    WHILE 1 = 1
+    BEGIN
+        DELETE FROM @step;
+        INSERT INTO @step EXEC [Ranking].[CalculateLevel] @level = @level;
+        SET @next = (SELECT TOP 1 xp FROM @step);
+        IF @next IS NULL OR @next <= 0 OR @xp < @next
+            BREAK;
+        SELECT @xp = @xp - @next, @level = @level + 1;
+    END
+
+    UPDATE [Ranking].[Level] SET xp = @xp, level = @level, last_xp = @now WHERE server_id = @serverid AND [user_id] = @userid;
+
+    COMMIT TRANSACTION;
+
+    IF @level = @old_level
+    BEGIN
+        SELECT -1, CAST(NULL AS DECIMAL(20, 0)), CAST(NULL AS DECIMAL(20, 0));
+        RETURN;
+    END
+
+    DECLARE @old_rank DECIMAL(20, 0) = (SELECT TOP 1 role_id FROM [Ranking].[Role] WHERE server_id = @serverid AND min_level <= @old_level ORDER BY min_level DESC);
+    DECLARE @new_rank DECIMAL(20, 0) = (SELECT TOP 1 role_id FROM [Ranking].[Role] WHERE server_id = @serverid AND min_level <= @level ORDER BY min_level DESC);
+
+    IF @old_rank = @new_rank
+        SELECT @old_rank = NULL, @new_rank = NULL;
+
+    SELECT @level, @old_rank, @new_rank;
+END
+GO
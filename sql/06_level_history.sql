@@ -0,0 +1,12 @@
+-- One experience snapshot per member per day, for rank history charts and trending users.
+
+CREATE TABLE [Ranking].[LevelHistory] (
+    server_id DECIMAL(20, 0) NOT NULL,
+    [user_id] DECIMAL(20, 0) NOT NULL,
+    snapshot_date DATE NOT NULL,
+    level INT NOT NULL,
+    xp INT NOT NULL,
+    CONSTRAINT [PK_Ranking_LevelHistory] PRIMARY KEY (server_id, [user_id], snapshot_date)
+);
+
+CREATE INDEX [IX_Ranking_LevelHistory_Date] ON [Ranking].[LevelHistory] (snapshot_date);
@@ -0,0 +1,9 @@
+-- Members and roles that don't earn experience and are left off the leaderboard.
+-- target_id is a user when is_role is 0, and a role otherwise.
+
+CREATE TABLE [Ranking].[Exclusion] (
+    server_id DECIMAL(20, 0) NOT NULL,
+    target_id DECIMAL(20, 0) NOT NULL,
+    is_role BIT NOT NULL,
+    CONSTRAINT [PK_Ranking_Exclusion] PRIMARY KEY (server_id, target_id, is_role)
+);
@@ -0,0 +1,12 @@
+-- Per-server settings for the nightly rank role auto-fix. Servers without a row don't run it.
+
+CREATE TABLE [Ranking].[AutofixConfig] (
+    id DECIMAL(20, 0) NOT NULL PRIMARY KEY,
+    enabled BIT NOT NULL DEFAULT 0,
+    dry_run BIT NOT NULL DEFAULT 1,
+    fix_multiple BIT NOT NULL DEFAULT 0,
+    fix_remove BIT NOT NULL DEFAULT 0,
+    fix_demote BIT NOT NULL DEFAULT 0,
+    log_channel DECIMAL(20, 0) NULL,
+    last_run DATETIME2 NULL
+);
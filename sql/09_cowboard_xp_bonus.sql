@@ -0,0 +1,34 @@
+-- Bonus experience for the authors of cowboard posts, and what each post awarded so it can be taken back.
+-- Message rows from before this script awarded nothing, so they have no author either.
+
+ALTER TABLE [Cowboard].[Server] ADD xp_bonus INT NOT NULL DEFAULT 0;
+
+ALTER TABLE [Cowboard].[Message] ADD
+    xp_awarded INT NOT NULL DEFAULT 0,
+    author_id DECIMAL(20, 0) NULL;
+GO
+
+-- Same as before, with the bonus saved alongside the other settings.
+CREATE OR ALTER PROCEDURE [Cowboard].[UpdateServer]
+    @id DECIMAL(20, 0),
+    @channel DECIMAL(20, 0),
+    @add_threshold INT,
+    @remove_threshold INT,
+    @emote NVARCHAR(128),
+    @webhook_id DECIMAL(20, 0),
+    @webhook_token NVARCHAR(128),
+    @xp_bonus INT = 0
+AS
+BEGIN
+    SET NOCOUNT ON;
+
+    UPDATE [Cowboard].[Server]
+    SET channel = @channel, add_threshold = @add_threshold, remove_threshold = @remove_threshold, emote = @emote,
+        webhook_id = @webhook_id, webhook_token = @webhook_token, xp_bonus = @xp_bonus
+    WHERE id = @id;
+
+    IF @@ROWCOUNT = 0
+        INSERT INTO [Cowboard].[Server] (id, channel, add_threshold, remove_threshold, emote, webhook_id, webhook_token, xp_bonus)
+        VALUES (@id, @channel, @add_threshold, @remove_threshold, @emote, @webhook_id, @webhook_token, @xp_bonus);
+END
+GO
@@ -0,0 +1,33 @@
+-- Servers can have several named cowboards, each with their own settings and posts.
+-- Existing servers keep their settings as a board named `cowboard`, and their posts move onto it.
+
+CREATE TABLE [Cowboard].[Board] (
+    id DECIMAL(20, 0) NOT NULL,
+    name NVARCHAR(32) NOT NULL,
+    channel DECIMAL(20, 0) NULL,
+    add_threshold INT NOT NULL,
+    remove_threshold INT NOT NULL,
+    emote NVARCHAR(128) NOT NULL,
+    webhook_id DECIMAL(20, 0) NULL,
+    webhook_token NVARCHAR(128) NULL,
+    xp_bonus INT NOT NULL DEFAULT 0,
+    CONSTRAINT [PK_Cowboard_Board] PRIMARY KEY (id, name)
+);
+
+ALTER TABLE [Cowboard].[Message] ADD board NVARCHAR(32) NULL;
+GO
+
+SET XACT_ABORT ON;
+BEGIN TRANSACTION;
+
+INSERT INTO [Cowboard].[Board] (id, name, channel, add_threshold, remove_threshold, emote, webhook_id, webhook_token, xp_bonus)
+SELECT id, N'cowboard', channel, add_threshold, remove_threshold, emote, webhook_id, webhook_token, xp_bonus FROM [Cowboard].[Server];
+
+UPDATE [Cowboard].[Message] SET board = N'cowboard' WHERE board IS NULL;
+
+DELETE FROM [Cowboard].[Server];
+
+COMMIT TRANSACTION;
+GO
+
+ALTER TABLE [Cowboard].[Message] ALTER COLUMN board NVARCHAR(32) NOT NULL;
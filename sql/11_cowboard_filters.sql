@@ -0,0 +1,9 @@
+-- Per-board channel allow and deny lists. Boards with allowed channels only take posts from those.
+
+CREATE TABLE [Cowboard].[Filter] (
+    guild_id DECIMAL(20, 0) NOT NULL,
+    board NVARCHAR(32) NOT NULL,
+    channel_id DECIMAL(20, 0) NOT NULL,
+    allow BIT NOT NULL,
+    CONSTRAINT [PK_Cowboard_Filter] PRIMARY KEY (guild_id, board, channel_id)
+);
@@ -0,0 +1,11 @@
+-- Per-server rules for which reactions count towards the cowboard. Servers without a row count everything but self-reactions.
+-- min_account_age is in days and max_message_age in hours; 0 turns either off.
+
+CREATE TABLE [Cowboard].[Rules] (
+    guild_id DECIMAL(20, 0) NOT NULL PRIMARY KEY,
+    count_self BIT NOT NULL DEFAULT 0,
+    allow_bots BIT NOT NULL DEFAULT 1,
+    min_account_age INT NOT NULL DEFAULT 0,
+    required_role DECIMAL(20, 0) NULL,
+    max_message_age INT NOT NULL DEFAULT 0
+);
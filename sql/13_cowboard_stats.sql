@@ -0,0 +1,6 @@
+-- The most reactions each post reached and when it was posted, for `cowboard stats` and digests.
+-- Posts from before this script have neither; they only count towards all-time totals.
+
+ALTER TABLE [Cowboard].[Message] ADD
+    peak_reactions INT NULL,
+    posted_at DATETIME2 NULL;
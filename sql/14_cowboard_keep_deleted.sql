@@ -0,0 +1,3 @@
+-- Whether posts stay on the cowboard after the original message is deleted.
+
+ALTER TABLE [Cowboard].[Rules] ADD keep_deleted BIT NOT NULL DEFAULT 0;
@@ -0,0 +1,11 @@
+-- Each message can only be posted once per board; moo_message relies on this to settle races between reaction events.
+-- Duplicates left over from before are removed first, keeping the earliest post.
+
+WITH ranked AS (
+    SELECT ROW_NUMBER() OVER (PARTITION BY message_id, message_channel_id, guild_id, board ORDER BY posted_at, post_id) AS position
+    FROM [Cowboard].[Message]
+)
+DELETE FROM ranked WHERE position > 1;
+
+ALTER TABLE [Cowboard].[Message]
+    ADD CONSTRAINT [UQ_Cowboard_Message_Board] UNIQUE (message_id, message_channel_id, guild_id, board);
@@ -0,0 +1,9 @@
+-- Per-server cowboard digests of the top posts. Servers without a row, or without a channel, don't get one.
+-- period is a DigestPeriod: 0 weekly, 1 monthly.
+
+CREATE TABLE [Cowboard].[Digest] (
+    guild_id DECIMAL(20, 0) NOT NULL PRIMARY KEY,
+    channel DECIMAL(20, 0) NULL,
+    period TINYINT NOT NULL DEFAULT 0,
+    last_sent DATETIME2 NULL
+);
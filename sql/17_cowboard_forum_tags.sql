@@ -0,0 +1,3 @@
+-- Tags applied to posts when a board's channel is a forum, as a comma-separated list of up to five tag IDs.
+
+ALTER TABLE [Cowboard].[Board] ADD forum_tags NVARCHAR(128) NULL;
@@ -0,0 +1,9 @@
+-- Per-server music settings. Servers without a row start the player at full volume.
+
+CREATE SCHEMA [Music];
+GO
+
+CREATE TABLE [Music].[Config] (
+    id DECIMAL(20, 0) NOT NULL PRIMARY KEY,
+    default_volume INT NOT NULL DEFAULT 100
+);
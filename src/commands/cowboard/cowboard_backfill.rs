@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+use chrono::NaiveDate;
+use poise::ReplyHandle;
+use tracing::error;
+use serenity::model::id::{ChannelId, MessageId};
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::commands::cowboard::cowboard_handler::{backfill_message, source_allowed};
+
+const MAX_SCANNED: u32 = 10000;
+
+// Slash command replies can only be edited for 15 minutes, so stop a little before that.
+const EDIT_WINDOW: Duration = Duration::from_secs(14 * 60);
+
+// How often to post a new message with the progress once the reply can't be edited anymore.
+const FOLLOW_UP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// Keeps the user posted on a backfill, which can easily outlast the interaction it was started from.
+struct Progress<'a> {
+    ctx: CowContext<'a>,
+    reply: ReplyHandle<'a>,
+    started: Instant,
+    last_follow_up: Option<Instant>
+}
+
+impl<'a> Progress<'a> {
+    fn can_edit(&self) -> bool {
+        !matches!(self.ctx, poise::Context::Application(_)) || self.started.elapsed() < EDIT_WINDOW
+    }
+
+    // Failing to report shouldn't stop the backfill, so errors are only logged.
+    async fn update(&mut self, content: String, last: bool) {
+        let result = if self.can_edit() {
+            self.reply.edit(self.ctx, |m| m.content(content)).await.map(|_| ())
+        } else if last || self.last_follow_up.map(|o| o.elapsed() >= FOLLOW_UP_INTERVAL).unwrap_or(true) {
+            self.last_follow_up = Some(Instant::now());
+            self.ctx.channel_id().send_message(self.ctx.serenity_context(), |m| m.content(content)).await.map(|_| ())
+        } else {
+            Ok(())
+        };
+
+        if let Err(ex) = result {
+            error!("Failed to report backfill progress: {}", ex);
+        }
+    }
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Look through a channel's history for popular messages that never made it onto a cowboard."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn backfill(
+    ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
+    #[description = "The channel to look through; defaults to this one."] channel: Option<ChannelId>,
+    #[description = "How many messages to look through, up to 10000."] #[min = 1] #[max = 10000] limit: Option<u32>,
+    #[description = "Stop at messages before this date, like 2023-01-31."] since: Option<String>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let channel = channel.unwrap_or_else(|| ctx.channel_id());
+        let limit = limit.unwrap_or(1000).min(MAX_SCANNED);
+
+        let since = match since.map(|o| NaiveDate::parse_from_str(&o, "%Y-%m-%d")) {
+            Some(Ok(date)) => date.and_hms_opt(0, 0, 0).map(|o| o.timestamp()),
+            Some(Err(_)) => {
+                ctx.say("The date should look like 2023-01-31.").await?;
+                return Ok(());
+            }
+            None => None
+        };
+
+        if !ctx.guild().map(|g| g.channels.contains_key(&channel)).unwrap_or(false) {
+            ctx.say("Could not find channel in this server!").await?;
+            return Ok(())
+        }
+
+        let mut config = match db.get_cowboard_config(guild_id, &board).await {
+            Ok(Some(config)) => config,
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+                return Ok(())
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the cowboard settings... try again later?").await?;
+                error!("Failed to get cowboard: {}", ex);
+                return Ok(())
+            }
+        };
+
+        if config.channel.is_none() {
+            ctx.say("Cowboard channel is not set up!").await?;
+            return Ok(())
+        }
+
+        let serenity = ctx.serenity_context();
+        if !source_allowed(serenity, &config, channel).await? {
+            ctx.say(format!("Messages from <#{channel}> can't reach the `{board}` board; check its filters.")).await?;
+            return Ok(())
+        }
+
+        let mut progress = Progress {
+            ctx,
+            reply: ctx.say(format!("Looking through <#{channel}> for the `{board}` board...")).await?,
+            started: Instant::now(),
+            last_follow_up: None
+        };
+
+        // History comes newest first, so collect everything before posting it oldest first.
+        let mut scanned = 0;
+        let mut candidates = Vec::new();
+        let mut before: Option<MessageId> = None;
+        'scan: while scanned < limit {
+            let page = match channel.messages(ctx, |r| {
+                if let Some(before) = before {
+                    r.before(before);
+                }
+                r.limit(100)
+            }).await {
+                Ok(page) => page,
+                Err(ex) => {
+                    progress.update(format!("Couldn't read the history of <#{channel}>; maybe I do not have permissions?"), true).await;
+                    error!("Failed to get channel history: {}", ex);
+                    return Ok(())
+                }
+            };
+
+            if page.is_empty() {
+                break;
+            }
+            before = page.last().map(|o| o.id);
+
+            for message in page {
+                if since.map(|o| message.timestamp.unix_timestamp() < o).unwrap_or(false) {
+                    break 'scan;
+                }
+
+                scanned += 1;
+
+                // The raw count is an upper bound on what the board would count, so it's a cheap first pass.
+                let popular = message.reactions.iter()
+                    .any(|o| config.matches(&o.reaction_type) && o.count >= config.add_threshold as u64);
+                if popular {
+                    candidates.push(message);
+                }
+
+                if scanned >= limit {
+                    break 'scan;
+                }
+            }
+
+            progress.update(format!("Looked through {scanned} messages in <#{channel}>, found {} to check...", candidates.len()), false).await;
+        }
+
+        let total = candidates.len();
+        let mut posted = 0;
+        for (checked, message) in candidates.iter().rev().enumerate() {
+            match backfill_message(serenity, message, &mut config).await {
+                Ok(true) => {
+                    posted += 1;
+                    // Posting is the expensive part; don't flood the board's channel.
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                Ok(false) => {}
+                Err(ex) => {
+                    error!("Failed to backfill cowboard message: {}", ex);
+                }
+            }
+
+            if (checked + 1) % 10 == 0 {
+                progress.update(format!("Checked {} of {total} messages from <#{channel}>, posted {posted} so far...", checked + 1), false).await;
+            }
+        }
+
+        progress.update(format!("Finished backfilling the `{board}` board! Looked through {scanned} messages in <#{channel}> and posted {posted}."), true).await;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
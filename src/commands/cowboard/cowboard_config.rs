@@ -1,39 +1,53 @@
 use tracing::error;
 use crate::{CowContext, cowdb, Error};
-use serenity::model::channel::ReactionType;
-use serenity::model::id::ChannelId;
+use serenity::model::channel::{Channel, ReactionType};
+use serenity::model::id::{ChannelId, GuildId};
 use serenity::utils::MessageBuilder;
 use crate::{Database, db};
+use crate::commands::cowboard::cowboard_db_models::Cowboard;
+use crate::commands::cowboard::cowboard_rest::get_forum_tags;
+use crate::util::confirm;
 
 #[poise::command(
     prefix_command,
     slash_command,
-    description_localized("en-US", "Get the current settings for the cowboard."),
-    guild_only,
-    discard_spare_arguments
+    description_localized("en-US", "Get the current settings for the cowboards."),
+    guild_only
 )]
-pub async fn info(ctx: CowContext<'_>) -> Result<(), Error> {
-    info_code(ctx).await
+pub async fn info(
+    ctx: CowContext<'_>,
+    #[description = "The name of a board to show the settings of."] board: Option<String>)
+-> Result<(), Error> {
+    if let Some(board) = board {
+        board_info(ctx, &board).await
+    } else {
+        info_code(ctx).await
+    }
 }
 
 pub async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
     let db = cowdb!(ctx);
 
     if let Some(guild_id) = ctx.guild_id() {
-        if let Ok(config) = db.get_cowboard_config(guild_id).await {
+        if let Ok(boards) = db.get_cowboards(guild_id).await {
+            if boards.is_empty() {
+                ctx.say("There are no cowboards in this server; try `.cowboard create` to make one!").await?;
+                return Ok(());
+            }
+
             ctx.send(|m| {
                 m.embeds.clear();
-                m.embed(|e|
+                m.embed(|e| {
                     e
-                        .title("Cowboard Settings")
-                        .description("If the emote doesn't display properly below, you probably want to use a different one!")
-                        .field("Emote", &config.emote, true)
-                        .field("Raw Emote", MessageBuilder::new().push_mono(&config.emote).build(), true)
-                        .field("Channel", config.channel.map(|o| format!("<#{o}>")).unwrap_or_else(|| "No Cowboard Channel".to_string()), true)
-                        .field("Add Threshold", MessageBuilder::new().push_mono(config.add_threshold).build(), true)
-                        .field("Remove Threshold", MessageBuilder::new().push_mono(config.remove_threshold).build(), true)
-                        .field("Webhook", if config.webhook_id.is_some() && config.webhook_token.is_some() { "Enabled" } else { "Disabled" }, true)
-                )
+                        .title("Cowboards")
+                        .description("Use `.cowboard info <name>` to see a board's settings.");
+
+                    for board in &boards {
+                        e.field(&board.name, format!("{} in {}", board.emote, board.channel.map(|o| format!("<#{o}>")).unwrap_or_else(|| "No Cowboard Channel".to_string())), true);
+                    }
+
+                    e
+                })
             }).await?;
         } else {
             ctx.say("Failed to fetch Cowboard settings for this server...").await?;
@@ -45,6 +59,167 @@ pub async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+async fn board_info(ctx: CowContext<'_>, board: &str) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_cowboard_config(guild_id, board).await {
+            Ok(Some(config)) => {
+                let filters = db.get_cowboard_filters(guild_id, board).await.unwrap_or_default();
+                let list = |allow: bool| filters.iter()
+                    .filter(|o| o.allow == allow)
+                    .map(|o| format!("<#{}>", o.channel))
+                    .reduce(|a, b| format!("{a} {b}"))
+                    .unwrap_or_else(|| if allow { "Anywhere".to_string() } else { "None".to_string() });
+
+                ctx.send(|m| {
+                    m.embeds.clear();
+                    m.embed(|e|
+                        e
+                            .title(format!("Cowboard Settings: {}", config.name))
+                            .description("If the emote doesn't display properly below, you probably want to use a different one!")
+                            .field("Emote", &config.emote, true)
+                            .field("Raw Emote", MessageBuilder::new().push_mono(&config.emote).build(), true)
+                            .field("Channel", config.channel.map(|o| format!("<#{o}>")).unwrap_or_else(|| "No Cowboard Channel".to_string()), true)
+                            .field("Add Threshold", MessageBuilder::new().push_mono(config.add_threshold).build(), true)
+                            .field("Remove Threshold", MessageBuilder::new().push_mono(config.remove_threshold).build(), true)
+                            .field("Webhook", if config.webhook_id.is_some() && config.webhook_token.is_some() { "Enabled" } else { "Disabled" }, true)
+                            .field("XP Bonus", if config.xp_bonus > 0 { format!("{} xp", config.xp_bonus) } else { "Disabled".to_string() }, true)
+                            .field("Forum Tags", if config.forum_tags.is_empty() { "None".to_string() } else { format!("{} tags", config.forum_tags.len()) }, true)
+                            .field("Allowed Sources", list(true), false)
+                            .field("Denied Sources", list(false), false)
+                    )
+                }).await?;
+            }
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Err(_) => {
+                ctx.say("Failed to fetch Cowboard settings for this server...").await?;
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+// Boards can post in channels, forums, and threads; threads are kept separately in the cache.
+// Archived threads aren't cached at all, so anything else is looked up before giving up on it.
+async fn valid_target(ctx: &CowContext<'_>, channel: ChannelId) -> bool {
+    let cached = ctx.guild().map(|g| g.channels.contains_key(&channel) || g.threads.iter().any(|o| o.id == channel)).unwrap_or(false);
+    if cached {
+        return true;
+    }
+
+    match channel.to_channel(ctx.serenity_context()).await {
+        Ok(Channel::Guild(found)) => Some(found.guild_id) == ctx.guild_id(),
+        _ => false
+    }
+}
+
+// Two boards with the same emote would fight over every reaction.
+async fn emote_taken(db: &Database, guild_id: GuildId, board: &str, emoji: &ReactionType) -> Result<Option<String>, Error> {
+    Ok(db.get_cowboards(guild_id).await?
+        .into_iter()
+        .find(|o| o.name != board && o.matches(emoji))
+        .map(|o| o.name))
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "Create a new cowboard."),
+    required_permissions = "ADMINISTRATOR",
+    guild_only
+)]
+pub async fn create(
+    ctx: CowContext<'_>,
+    #[description = "A short name for the board, like \"funny\" or \"helpful\"."] board: String,
+    #[description = "An emote on the server or a default Discord emoji."] emoji: ReactionType,
+    #[description = "A channel to post in; defaults to this one."] channel: Option<ChannelId>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let board = board.to_lowercase();
+        let cowboard_channel = channel.unwrap_or_else(|| ctx.channel_id());
+
+        if board.is_empty() || board.len() > 32 || board.contains(char::is_whitespace) {
+            ctx.say("Board names must be one word, up to 32 characters long.").await?;
+            return Ok(())
+        }
+
+        if !valid_target(&ctx, cowboard_channel).await {
+            ctx.say("Could not find channel in this server!").await?;
+            return Ok(())
+        }
+
+        if db.get_cowboard_config(guild_id, &board).await?.is_some() {
+            ctx.say(format!("There's already a board named `{board}`.")).await?;
+            return Ok(())
+        }
+
+        if let Some(other) = emote_taken(&db, guild_id, &board, &emoji).await? {
+            ctx.say(format!("The `{other}` board already uses that emote.")).await?;
+            return Ok(())
+        }
+
+        let mut config = Cowboard::new(guild_id.0, &board);
+        config.emote = emoji.to_string();
+        config.channel = Some(cowboard_channel.0);
+
+        if let Err(ex) = db.update_cowboard(&config).await {
+            ctx.say("We couldn't create the cowboard, sorry... Try again later?").await?;
+            error!("Failed to create cowboard: {}", ex);
+        } else {
+            ctx.say(format!("Created the `{board}` board in <#{cowboard_channel}>! You may want to try using `.cowboard webhook {board}` to enable webhooks.")).await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "Delete a cowboard; its posts are left alone."),
+    required_permissions = "ADMINISTRATOR",
+    guild_only
+)]
+pub async fn delete(
+    ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if !confirm(&ctx, &format!("Are you sure you want to delete the `{board}` board?")).await? {
+            return Ok(())
+        }
+
+        match db.delete_cowboard(guild_id, &board).await {
+            Ok(true) => {
+                ctx.say(format!("Deleted the `{board}` board.")).await?;
+            }
+            Ok(false) => {
+                ctx.say(format!("There's no board named `{board}`.")).await?;
+            }
+            Err(ex) => {
+                ctx.say("We couldn't delete the cowboard, sorry... Try again later?").await?;
+                error!("Failed to delete cowboard: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
 #[poise::command(
     prefix_command,
     slash_command,
@@ -54,13 +229,22 @@ pub async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
 )]
 pub async fn emote(
     ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
     #[description = "An emote on the server or a default Discord emoji."] emoji: ReactionType)
 -> Result<(), Error> {
     let db = cowdb!(ctx);
 
     if let Some(guild_id) = ctx.guild_id() {
-        match db.get_cowboard_config(guild_id).await {
-            Ok(mut config) => {
+        match db.get_cowboard_config(guild_id, &board).await {
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Ok(Some(mut config)) => {
+                if let Some(other) = emote_taken(&db, guild_id, &board, &emoji).await? {
+                    ctx.say(format!("The `{other}` board already uses that emote.")).await?;
+                    return Ok(())
+                }
+
                 config.emote = emoji.to_string();
                 if let Err(ex) = db.update_cowboard(&config).await {
                     ctx.say("We couldn't update the cowboard, sorry... Try again later?").await?;
@@ -90,6 +274,7 @@ pub async fn emote(
 )]
 pub async fn addthreshold(
     ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
     #[description = "A positive number, greater than the removal bound."] #[min = 1] add_threshold: i32)
 -> Result<(), Error> {
     let db = cowdb!(ctx);
@@ -100,8 +285,11 @@ pub async fn addthreshold(
     }
 
     if let Some(guild_id) = ctx.guild_id() {
-        match db.get_cowboard_config(guild_id).await {
-            Ok(mut config) => {
+        match db.get_cowboard_config(guild_id, &board).await {
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Ok(Some(mut config)) => {
                 if add_threshold < config.remove_threshold {
                     ctx.say(format!("The minimum number of reactions required to add must be greater than or equal to the removal limit (currently set to {}).", config.remove_threshold)).await?;
                     return Ok(())
@@ -137,6 +325,7 @@ pub async fn addthreshold(
 )]
 pub async fn removethreshold(
     ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
     #[description = "A positive number, less than the addition bound."] #[min = 1] remove_threshold: i32)
 -> Result<(), Error> {
     let db = cowdb!(ctx);
@@ -147,8 +336,11 @@ pub async fn removethreshold(
     }
 
     if let Some(guild_id) = ctx.guild_id() {
-        match db.get_cowboard_config(guild_id).await {
-            Ok(mut config) => {
+        match db.get_cowboard_config(guild_id, &board).await {
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Ok(Some(mut config)) => {
                 if remove_threshold > config.add_threshold {
                     ctx.say(format!("The maximum number of reactions required to remove must be less than or equal to the add limit (currently set to {}).", config.add_threshold)).await?;
                     return Ok(())
@@ -175,6 +367,54 @@ pub async fn removethreshold(
     Ok(())
 }
 
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set the bonus experience given to authors of messages posted to the cowboard."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn xpbonus(
+    ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
+    #[description = "The amount of experience to award; 0 disables the bonus."] #[min = 0] #[max = 100000] xp_bonus: i32)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if xp_bonus < 0 {
+        ctx.say("The given number must be positive or zero.").await?;
+        return Ok(())
+    }
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_cowboard_config(guild_id, &board).await {
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Ok(Some(mut config)) => {
+                config.xp_bonus = xp_bonus;
+
+                if let Err(ex) = db.update_cowboard(&config).await {
+                    ctx.say("We couldn't update the cowboard, sorry... Try again later?").await?;
+                    error!("Failed to update cowboard: {}", ex);
+                } else if xp_bonus == 0 {
+                    ctx.say("Disabled the cowboard experience bonus.").await?;
+                } else {
+                    ctx.say(format!("Messages posted to the cowboard will now award {xp_bonus} xp to their author.")).await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the cowboard settings... try again later?").await?;
+                error!("Failed to get cowboard: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
 #[poise::command(
     prefix_command,
     slash_command,
@@ -184,6 +424,7 @@ pub async fn removethreshold(
 )]
 pub async fn channel(
     ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
     #[description = "A channel to set the Cowboard channel to."] channel: Option<ChannelId>)
 -> Result<(), Error> {
     let db = cowdb!(ctx);
@@ -191,13 +432,16 @@ pub async fn channel(
     if let Some(guild_id) = ctx.guild_id() {
         let cowboard_channel = channel.unwrap_or_else(|| ctx.channel_id());
 
-        if !ctx.guild().map(|g| g.channels.contains_key(&cowboard_channel)).unwrap_or(false) {
+        if !valid_target(&ctx, cowboard_channel).await {
             ctx.say("Could not find channel in this server!").await?;
             return Ok(())
         }
 
-        match db.get_cowboard_config(guild_id).await {
-            Ok(mut config) => {
+        match db.get_cowboard_config(guild_id, &board).await {
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Ok(Some(mut config)) => {
                 config.channel = Some(cowboard_channel.0);
                 config.webhook_id = None;
                 config.webhook_token = None;
@@ -206,7 +450,7 @@ pub async fn channel(
                     ctx.say("We couldn't update the cowboard, sorry... Try again later?").await?;
                     error!("Failed to update cowboard: {}", ex);
                 } else {
-                    ctx.say("Successfully updated channel! You may want to check webhooks; try using `.cowboard webhook` to enable it.").await?;
+                    ctx.say(format!("Successfully updated channel! You may want to check webhooks; try using `.cowboard webhook {board}` to enable it.")).await?;
                 }
             }
             Err(ex) => {
@@ -229,18 +473,34 @@ pub async fn channel(
     required_permissions = "ADMINISTRATOR",
     discard_spare_arguments
 )]
-pub async fn webhook(ctx: CowContext<'_>) -> Result<(), Error> {
+pub async fn webhook(
+    ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String)
+-> Result<(), Error> {
     let db = cowdb!(ctx);
 
     if let Some(guild) = ctx.guild() {
-        match db.get_cowboard_config(guild.id).await {
-            Ok(mut config) => {
+        match db.get_cowboard_config(guild.id, &board).await {
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Ok(Some(mut config)) => {
                 if config.channel.is_none() {
                     ctx.say("Cowboard channel is not set up!").await?;
                     return Ok(());
                 }
 
-                let channel = ChannelId::from(config.channel.unwrap());
+                // Threads can't have webhooks of their own, so their parent's is used with the thread's ID.
+                let target = ChannelId::from(config.channel.unwrap());
+                let channel = match guild.threads.iter().find(|o| o.id == target) {
+                    Some(thread) => thread.parent_id.unwrap_or(target),
+                    None if guild.channels.contains_key(&target) => target,
+                    // Archived threads aren't in the cache.
+                    None => match target.to_channel(ctx.serenity_context()).await {
+                        Ok(Channel::Guild(thread)) if thread.thread_metadata.is_some() => thread.parent_id.unwrap_or(target),
+                        _ => target
+                    }
+                };
                 match guild.channels(&ctx).await {
                     Ok(guild_channels) => {
                         if let Some(guild_channel) = guild_channels.get(&channel)
@@ -292,4 +552,150 @@ pub async fn webhook(ctx: CowContext<'_>) -> Result<(), Error> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set the tags applied to posts when a cowboard posts in a forum."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn tags(
+    ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
+    #[description = "Tag names separated by commas; leave empty to clear them."] #[rest] names: Option<String>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_cowboard_config(guild_id, &board).await {
+            Ok(None) => {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            }
+            Ok(Some(mut config)) => {
+                let wanted = names.unwrap_or_default()
+                    .split(',')
+                    .map(|o| o.trim().to_lowercase())
+                    .filter(|o| !o.is_empty())
+                    .collect::<Vec<_>>();
+
+                if wanted.is_empty() {
+                    config.forum_tags.clear();
+                } else {
+                    let forum = match config.channel {
+                        Some(channel) => channel,
+                        None => {
+                            ctx.say("Cowboard channel is not set up!").await?;
+                            return Ok(())
+                        }
+                    };
+
+                    let available = match get_forum_tags(&ctx.serenity_context().http, forum).await {
+                        Ok(available) if !available.is_empty() => available,
+                        Ok(_) => {
+                            ctx.say(format!("<#{forum}> doesn't have any tags; is it a forum?")).await?;
+                            return Ok(())
+                        }
+                        Err(ex) => {
+                            ctx.say(format!("We couldn't get the tags for <#{forum}>; is it a forum?")).await?;
+                            error!("Failed to get forum tags: {}", ex);
+                            return Ok(())
+                        }
+                    };
+
+                    let mut found = Vec::new();
+                    for name in &wanted {
+                        match available.iter().find(|o| o.name.to_lowercase() == *name).and_then(|o| o.id.parse().ok()) {
+                            Some(id) => found.push(id),
+                            None => {
+                                ctx.say(format!("<#{forum}> doesn't have a tag named `{name}`.")).await?;
+                                return Ok(())
+                            }
+                        }
+                    }
+
+                    // Discord allows up to five tags on a post.
+                    found.truncate(5);
+                    config.forum_tags = found;
+                }
+
+                if let Err(ex) = db.update_cowboard(&config).await {
+                    ctx.say("We couldn't update the cowboard, sorry... Try again later?").await?;
+                    error!("Failed to update cowboard: {}", ex);
+                } else if config.forum_tags.is_empty() {
+                    ctx.say(format!("Posts from the `{board}` board won't be tagged.")).await?;
+                } else {
+                    ctx.say(format!("Posts from the `{board}` board will be tagged with {} tags.", config.forum_tags.len())).await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the cowboard settings... try again later?").await?;
+                error!("Failed to get cowboard: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Allow or deny messages from a channel or category reaching a cowboard."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn filter(
+    ctx: CowContext<'_>,
+    #[description = "The name of the board."] board: String,
+    #[description = "Either allow, deny, or remove."] action: String,
+    #[description = "A channel or category."] channel: ChannelId)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let allow = match action.to_lowercase().as_str() {
+            "allow" => Some(true),
+            "deny" | "block" => Some(false),
+            "remove" | "clear" => None,
+            _ => {
+                ctx.say("The action must be one of allow, deny, or remove.").await?;
+                return Ok(())
+            }
+        };
+
+        if !valid_target(&ctx, channel).await {
+            ctx.say("Could not find channel in this server!").await?;
+            return Ok(())
+        }
+
+        if db.get_cowboard_config(guild_id, &board).await?.is_none() {
+            ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+            return Ok(())
+        }
+
+        match db.set_cowboard_filter(guild_id, &board, channel, allow).await {
+            Ok(changed) => {
+                let content = match allow {
+                    Some(true) => format!("Only allowed sources like <#{channel}> can reach the `{board}` board now."),
+                    Some(false) => format!("Messages from <#{channel}> will no longer reach the `{board}` board."),
+                    None if changed => format!("Removed <#{channel}> from the `{board}` board's filters."),
+                    None => format!("<#{channel}> wasn't in the `{board}` board's filters.")
+                };
+
+                ctx.say(content).await?;
+            }
+            Err(ex) => {
+                ctx.say("We couldn't update the cowboard, sorry... Try again later?").await?;
+                error!("Failed to update cowboard filter: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
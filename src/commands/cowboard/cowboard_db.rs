@@ -10,114 +10,397 @@ use rust_decimal::{
 };
 use rust_decimal::prelude::ToPrimitive;
 use serenity::model::id::MessageId;
+use tiberius::Row;
 
 use crate::Database;
 use crate::commands::cowboard::cowboard_db_models::*;
 
+fn to_cowboard(id: u64, item: &Row) -> Cowboard {
+    let name: &str = item.get(0).unwrap();
+    let channel_id: Option<Decimal> = item.get(1);
+    let emote_str: &str = item.get(4).unwrap();
+    let webhook_id: Option<Decimal> = item.get(5);
+    let webhook_token: Option<&str> = item.get(6);
+    let forum_tags: Option<&str> = item.get(8);
+    Cowboard {
+        id,
+        name: name.to_string(),
+        channel: channel_id.and_then(|o| o.to_u64()),
+        add_threshold: item.get(2).unwrap(),
+        remove_threshold: item.get(3).unwrap(),
+        emote: emote_str.to_string(),
+        webhook_id: webhook_id.and_then(|o| o.to_u64()),
+        webhook_token: webhook_token.map(|o| o.to_string()),
+        xp_bonus: item.get(7).unwrap_or(0),
+        forum_tags: forum_tags.map(|o| o.split(',').filter_map(|t| t.parse().ok()).collect()).unwrap_or_default()
+    }
+}
+
+// Rows from before authors and peaks were tracked have them as NULL.
+fn to_cowboard_message(item: &Row) -> CowboardMessage {
+    let board: &str = item.get(5).unwrap();
+    CowboardMessage {
+        message_id: item.get(0).and_then(|u: Decimal| u.to_u64()).unwrap(),
+        message_channel_id: item.get(1).and_then(|u: Decimal| u.to_u64()).unwrap(),
+        post_id: item.get(2).and_then(|u: Decimal| u.to_u64()).unwrap(),
+        post_channel_id: item.get(3).and_then(|u: Decimal| u.to_u64()).unwrap(),
+        guild_id: item.get(4).and_then(|u: Decimal| u.to_u64()).unwrap(),
+        board: board.to_string(),
+        xp_awarded: item.get(6).unwrap_or(0),
+        author_id: item.get(7).and_then(|u: Decimal| u.to_u64()).unwrap_or(0),
+        peak_reactions: item.get(8).unwrap_or(0)
+    }
+}
+
+fn to_digest(id: u64, row: &Row) -> CowboardDigest {
+    let channel: Option<Decimal> = row.get(0);
+    let period: u8 = row.get(1).unwrap();
+    CowboardDigest {
+        id,
+        channel: channel.and_then(|o| o.to_u64()),
+        period: DigestPeriod::try_from(period).unwrap_or(DigestPeriod::Weekly),
+        last_sent: row.get(2)
+    }
+}
+
 // Separating the database into different modules so it doesn't become a 2000 line file.
 impl Database {
-    pub async fn get_cowboard_config(&self, server_id: GuildId) -> Result<Cowboard, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn get_cowboards(&self, server_id: GuildId) -> Result<Vec<Cowboard>, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
         let res = conn.query(
-            "SELECT channel, add_threshold, remove_threshold, emote, webhook_id, webhook_token FROM [Cowboard].[Server] WHERE id = @P1",
+            "SELECT name, channel, add_threshold, remove_threshold, emote, webhook_id, webhook_token, xp_bonus, forum_tags FROM [Cowboard].[Board] WHERE id = @P1 ORDER BY name",
             &[&server])
             .await?
+            .into_first_result()
+            .await?
+            .iter()
+            .map(|item| to_cowboard(server_id.0, item))
+            .collect();
+
+        Ok(res)
+    }
+
+    pub async fn get_cowboard_config(&self, server_id: GuildId, name: &str) -> Result<Option<Cowboard>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT name, channel, add_threshold, remove_threshold, emote, webhook_id, webhook_token, xp_bonus, forum_tags FROM [Cowboard].[Board] WHERE id = @P1 AND name = @P2",
+            &[&server, &name])
+            .await?
             .into_row()
             .await?;
 
-        let mut out = Cowboard::new(server_id.0);
+        Ok(res.map(|item| to_cowboard(server_id.0, &item)))
+    }
+
+    pub async fn update_cowboard(&self, config: &Cowboard) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(config.id).unwrap();
+        let channel = config.channel.map(|o| Decimal::from_u64(o).unwrap());
+        let webhook_id = config.webhook_id.map(|o| Decimal::from_u64(o).unwrap());
+        let forum_tags = if config.forum_tags.is_empty() {
+            None
+        } else {
+            Some(config.forum_tags.iter().map(|o| o.to_string()).collect::<Vec<_>>().join(","))
+        };
+
+        conn.execute(
+            "UPDATE [Cowboard].[Board] SET channel = @P3, add_threshold = @P4, remove_threshold = @P5, emote = @P6, webhook_id = @P7, webhook_token = @P8, xp_bonus = @P9, forum_tags = @P10 WHERE id = @P1 AND name = @P2; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Cowboard].[Board] (id, name, channel, add_threshold, remove_threshold, emote, webhook_id, webhook_token, xp_bonus, forum_tags) VALUES (@P1, @P2, @P3, @P4, @P5, @P6, @P7, @P8, @P9, @P10);",
+            &[&server, &config.name, &channel, &config.add_threshold, &config.remove_threshold, &config.emote, &webhook_id, &config.webhook_token, &config.xp_bonus, &forum_tags])
+            .await?;
+
+        Ok(())
+    }
+
+    // Removes the board along with its record of posted messages; the posts themselves stay up.
+    pub async fn delete_cowboard(&self, server_id: GuildId, name: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let total = conn.execute(
+            "DELETE FROM [Cowboard].[Message] WHERE guild_id = @P1 AND board = @P2; \
+            DELETE FROM [Cowboard].[Filter] WHERE guild_id = @P1 AND board = @P2; \
+            DELETE FROM [Cowboard].[Board] WHERE id = @P1 AND name = @P2;",
+            &[&server, &name])
+            .await?
+            .rows_affected()
+            .last()
+            .copied()
+            .unwrap_or(0);
+
+        Ok(total > 0)
+    }
+
+    pub async fn get_cowboard_filters(&self, server_id: GuildId, board: &str) -> Result<Vec<CowboardFilter>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT channel_id, allow FROM [Cowboard].[Filter] WHERE guild_id = @P1 AND board = @P2",
+            &[&server, &board])
+            .await?
+            .into_first_result()
+            .await?
+            .into_iter()
+            .map(|row| CowboardFilter {
+                channel: row.get(0).and_then(|u: Decimal| u.to_u64()).unwrap(),
+                allow: row.get(1).unwrap()
+            })
+            .collect();
+
+        Ok(res)
+    }
+
+    // None removes the channel from both lists.
+    pub async fn set_cowboard_filter(&self, server_id: GuildId, board: &str, channel: ChannelId, allow: Option<bool>) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let channel = Decimal::from_u64(channel.0).unwrap();
+
+        let total = if let Some(allow) = allow {
+            conn.execute(
+                "UPDATE [Cowboard].[Filter] SET allow = @P4 WHERE guild_id = @P1 AND board = @P2 AND channel_id = @P3; \
+                IF @@ROWCOUNT = 0 INSERT INTO [Cowboard].[Filter] (guild_id, board, channel_id, allow) VALUES (@P1, @P2, @P3, @P4);",
+                &[&server, &board, &channel, &allow])
+                .await?
+                .total()
+        } else {
+            conn.execute(
+                "DELETE FROM [Cowboard].[Filter] WHERE guild_id = @P1 AND board = @P2 AND channel_id = @P3",
+                &[&server, &board, &channel])
+                .await?
+                .total()
+        };
+
+        Ok(total > 0)
+    }
+
+    pub async fn get_cowboard_rules(&self, server_id: GuildId) -> Result<CowboardRules, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT count_self, allow_bots, min_account_age, required_role, max_message_age, keep_deleted FROM [Cowboard].[Rules] WHERE guild_id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        let mut out = CowboardRules::new(server_id.0);
 
         if let Some(item) = res {
-            let channel_id: Option<Decimal> = item.get(0);
-            let emote_str: &str = item.get(3).unwrap();
-            let webhook_id: Option<Decimal> = item.get(4);
-            let webhook_token: Option<&str> = item.get(5);
-            out = Cowboard {
-                id: server_id.0,
-                channel: channel_id.and_then(|o| o.to_u64()),
-                add_threshold: item.get(1).unwrap(),
-                remove_threshold: item.get(2).unwrap(),
-                emote: emote_str.to_string(),
-                webhook_id: webhook_id.and_then(|o| o.to_u64()),
-                webhook_token: webhook_token.map(|o| o.to_string())
-            };
+            let required_role: Option<Decimal> = item.get(3);
+            out.count_self = item.get(0).unwrap();
+            out.allow_bots = item.get(1).unwrap();
+            out.min_account_age = item.get(2).unwrap();
+            out.required_role = required_role.and_then(|o| o.to_u64());
+            out.max_message_age = item.get(4).unwrap();
+            out.keep_deleted = item.get(5).unwrap_or(false);
         }
 
         Ok(out)
     }
 
-    pub async fn update_cowboard(&self, config: &Cowboard) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn update_cowboard_rules(&self, rules: &CowboardRules) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
-        let server = Decimal::from_u64(config.id).unwrap();
-        let channel = config.channel.map(|o| Decimal::from_u64(o).unwrap());
-        let webhook_id = config.webhook_id.map(|o| Decimal::from_u64(o).unwrap());
+        let server = Decimal::from_u64(rules.id).unwrap();
+        let required_role = rules.required_role.map(|o| Decimal::from_u64(o).unwrap());
 
-        conn.query(
-            "EXEC [Cowboard].[UpdateServer] @id = @P1, @channel = @P2, @add_threshold = @P3, @remove_threshold = @P4, @emote = @P5, @webhook_id = @P6, @webhook_token = @P7",
-            &[&server, &channel, &config.add_threshold, &config.remove_threshold, &config.emote, &webhook_id, &config.webhook_token])
+        conn.execute(
+            "UPDATE [Cowboard].[Rules] SET count_self = @P2, allow_bots = @P3, min_account_age = @P4, required_role = @P5, max_message_age = @P6, keep_deleted = @P7 WHERE guild_id = @P1; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Cowboard].[Rules] (guild_id, count_self, allow_bots, min_account_age, required_role, max_message_age, keep_deleted) VALUES (@P1, @P2, @P3, @P4, @P5, @P6, @P7);",
+            &[&server, &rules.count_self, &rules.allow_bots, &rules.min_account_age, &required_role, &rules.max_message_age, &rules.keep_deleted])
             .await?;
 
         Ok(())
     }
 
-    pub async fn get_cowboard_message(&self, message: MessageId, channel: ChannelId, guild: GuildId) -> Result<Option<CowboardMessage>, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn get_cowboard_digest(&self, server_id: GuildId) -> Result<CowboardDigest, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT channel, period, last_sent FROM [Cowboard].[Digest] WHERE guild_id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        Ok(res.map(|row| to_digest(server_id.0, &row)).unwrap_or_else(|| CowboardDigest::new(server_id.0)))
+    }
+
+    pub async fn update_cowboard_digest(&self, digest: &CowboardDigest) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(digest.id).unwrap();
+        let channel = digest.channel.map(|o| Decimal::from_u64(o).unwrap());
+        let period = digest.period as u8;
+
+        conn.execute(
+            "UPDATE [Cowboard].[Digest] SET channel = @P2, period = @P3, last_sent = @P4 WHERE guild_id = @P1; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Cowboard].[Digest] (guild_id, channel, period, last_sent) VALUES (@P1, @P2, @P3, @P4);",
+            &[&server, &channel, &period, &digest.last_sent])
+            .await?;
+
+        Ok(())
+    }
+
+    // Digests whose period has passed since they were last sent.
+    pub async fn get_due_digests(&self) -> Result<Vec<CowboardDigest>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let weekly = DigestPeriod::Weekly as u8;
+        let monthly = DigestPeriod::Monthly as u8;
+        let res = conn.query(
+            "SELECT channel, period, last_sent, guild_id FROM [Cowboard].[Digest] \
+            WHERE channel IS NOT NULL AND (last_sent IS NULL \
+            OR (period = @P1 AND last_sent <= DATEADD(WEEK, -1, SYSUTCDATETIME())) \
+            OR (period = @P2 AND last_sent <= DATEADD(MONTH, -1, SYSUTCDATETIME())))",
+            &[&weekly, &monthly])
+            .await?
+            .into_first_result()
+            .await?
+            .into_iter()
+            .map(|row| {
+                let id: Decimal = row.get(3).unwrap();
+                to_digest(id.to_u64().unwrap(), &row)
+            })
+            .collect();
+
+        Ok(res)
+    }
+
+    pub async fn get_cowboard_message(&self, message: MessageId, channel: ChannelId, guild: GuildId, board: &str) -> Result<Option<CowboardMessage>, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let message_decimal = Decimal::from_u64(message.0).unwrap();
         let channel_decimal = Decimal::from_u64(channel.0).unwrap();
         let server_decimal = Decimal::from_u64(guild.0).unwrap();
         let res = conn.query(
-            "SELECT post_id, post_channel_id FROM [Cowboard].[Message] WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P3",
-            &[&message_decimal, &channel_decimal, &server_decimal])
+            "SELECT message_id, message_channel_id, post_id, post_channel_id, guild_id, board, xp_awarded, author_id, peak_reactions FROM [Cowboard].[Message] WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P3 AND board = @P4",
+            &[&message_decimal, &channel_decimal, &server_decimal, &board])
             .await?
             .into_row()
             .await?;
 
-        let mut out: Option<CowboardMessage> = None;
+        Ok(res.map(|item| to_cowboard_message(&item)))
+    }
 
-        if let Some(item) = res {
-            let post_id = item.get(0).and_then(|u: Decimal| u.to_u64()).unwrap();
-            let post_channel_id = item.get(1).and_then(|u: Decimal| u.to_u64()).unwrap();
-
-            out = Some(CowboardMessage {
-                message_id: message.0,
-                message_channel_id: channel.0,
-                post_id,
-                post_channel_id,
-                guild_id: guild.0
-            });
-        }
+    // Every board's post of the message, for things that affect all of them at once.
+    pub async fn get_cowboard_posts(&self, message: MessageId, channel: ChannelId, guild: GuildId) -> Result<Vec<CowboardMessage>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let message_decimal = Decimal::from_u64(message.0).unwrap();
+        let channel_decimal = Decimal::from_u64(channel.0).unwrap();
+        let server_decimal = Decimal::from_u64(guild.0).unwrap();
+        let res = conn.query(
+            "SELECT message_id, message_channel_id, post_id, post_channel_id, guild_id, board, xp_awarded, author_id, peak_reactions FROM [Cowboard].[Message] WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P3",
+            &[&message_decimal, &channel_decimal, &server_decimal])
+            .await?
+            .into_first_result()
+            .await?
+            .iter()
+            .map(to_cowboard_message)
+            .collect();
 
-        Ok(out)
+        Ok(res)
     }
 
-    pub async fn moo_message(&self, message: MessageId, channel: ChannelId, post_message: MessageId, post_channel: ChannelId, guild: GuildId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Only ever raises the stored count, so it tracks the most reactions the message has had.
+    pub async fn record_cowboard_peak(&self, message: MessageId, channel: ChannelId, guild: GuildId, board: &str, reactions: i32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let message = Decimal::from_u64(message.0).unwrap();
         let channel = Decimal::from_u64(channel.0).unwrap();
-        let post_message = Decimal::from_u64(post_message.0).unwrap();
-        let post_channel = Decimal::from_u64(post_channel.0).unwrap();
         let server = Decimal::from_u64(guild.0).unwrap();
 
-        conn.query(
-            "INSERT INTO [Cowboard].[Message] (message_id, message_channel_id, post_id, post_channel_id, guild_id) VALUES (@P1, @P2, @P3, @P4, @P5)",
-            &[&message, &channel, &post_message, &post_channel, &server])
+        conn.execute(
+            "UPDATE [Cowboard].[Message] SET peak_reactions = @P5 WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P3 AND board = @P4 AND (peak_reactions IS NULL OR peak_reactions < @P5)",
+            &[&message, &channel, &server, &board, &reactions])
+            .await?;
+
+        Ok(())
+    }
+
+    // Leaving the board empty covers every board, and 0 days covers all time.
+    pub async fn get_cowboard_stats(&self, guild: GuildId, board: Option<&str>, days: i32) -> Result<CowboardStats, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(guild.0).unwrap();
+        let board = board.unwrap_or("");
+        let res = conn.query(
+            r#"
+            DECLARE @posts TABLE (message_id NUMERIC(20, 0), message_channel_id NUMERIC(20, 0), post_id NUMERIC(20, 0), post_channel_id NUMERIC(20, 0), guild_id NUMERIC(20, 0), board NVARCHAR(32), xp_awarded INT, author_id NUMERIC(20, 0), peak_reactions INT);
+            INSERT INTO @posts
+                SELECT message_id, message_channel_id, post_id, post_channel_id, guild_id, board, xp_awarded, author_id, peak_reactions FROM [Cowboard].[Message]
+                WHERE guild_id = @P1 AND (@P2 = '' OR board = @P2) AND (@P3 = 0 OR posted_at >= DATEADD(DAY, -@P3, SYSUTCDATETIME()));
+
+            SELECT COUNT(1), ISNULL(SUM(ISNULL(peak_reactions, 0)), 0) FROM @posts;
+            SELECT TOP 5 author_id, COUNT(1) AS posts FROM @posts WHERE author_id IS NOT NULL GROUP BY author_id ORDER BY posts DESC;
+            SELECT TOP 5 message_channel_id, COUNT(1) AS posts FROM @posts GROUP BY message_channel_id ORDER BY posts DESC;
+            SELECT TOP 5 * FROM @posts WHERE peak_reactions IS NOT NULL ORDER BY peak_reactions DESC;
+            "#,
+            &[&server, &board, &days])
+            .await?
+            .into_results()
+            .await?;
+
+        let totals = res.first().and_then(|o| o.first());
+        let counts = |set: usize| res.get(set).unwrap().iter()
+            .map(|row| (row.get(0).and_then(|u: Decimal| u.to_u64()).unwrap(), row.get::<i32, _>(1).unwrap()))
+            .collect::<Vec<_>>();
+
+        Ok(CowboardStats {
+            posts: totals.and_then(|o| o.get(0)).unwrap_or(0),
+            reactions: totals.and_then(|o| o.get(1)).unwrap_or(0),
+            top_users: counts(1),
+            top_channels: counts(2),
+            top_messages: res.get(3).unwrap().iter().map(to_cowboard_message).collect()
+        })
+    }
+
+    // Returns false if the message is already on the board; the lock hints stop two inserts racing past the check.
+    pub async fn moo_message(&self, record: &CowboardMessage) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let message = Decimal::from_u64(record.message_id).unwrap();
+        let channel = Decimal::from_u64(record.message_channel_id).unwrap();
+        let post_message = Decimal::from_u64(record.post_id).unwrap();
+        let post_channel = Decimal::from_u64(record.post_channel_id).unwrap();
+        let server = Decimal::from_u64(record.guild_id).unwrap();
+        let author = Decimal::from_u64(record.author_id).unwrap();
+
+        let res = conn.execute(
+            "INSERT INTO [Cowboard].[Message] (message_id, message_channel_id, post_id, post_channel_id, guild_id, board, xp_awarded, author_id, peak_reactions, posted_at) \
+            SELECT @P1, @P2, @P3, @P4, @P5, @P6, @P7, @P8, @P9, SYSUTCDATETIME() \
+            WHERE NOT EXISTS (SELECT 1 FROM [Cowboard].[Message] WITH (UPDLOCK, HOLDLOCK) WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P5 AND board = @P6)",
+            &[&message, &channel, &post_message, &post_channel, &server, &record.board, &record.xp_awarded, &author, &record.peak_reactions])
+            .await;
+
+        // The unique key on the message and board (sql/15_cowboard_message_unique.sql) is what actually settles races.
+        match res {
+            Ok(res) => Ok(res.total() > 0),
+            Err(tiberius::error::Error::Server(ex)) if ex.code() == 2627 || ex.code() == 2601 => Ok(false),
+            Err(ex) => Err(ex.into())
+        }
+    }
+
+    pub async fn set_cowboard_xp_awarded(&self, message: MessageId, channel: ChannelId, guild: GuildId, board: &str, xp_awarded: i32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let message = Decimal::from_u64(message.0).unwrap();
+        let channel = Decimal::from_u64(channel.0).unwrap();
+        let server = Decimal::from_u64(guild.0).unwrap();
+
+        conn.execute(
+            "UPDATE [Cowboard].[Message] SET xp_awarded = @P5 WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P3 AND board = @P4",
+            &[&message, &channel, &server, &board, &xp_awarded])
             .await?;
 
         Ok(())
     }
 
-    pub async fn unmoo_message(&self, message: MessageId, channel: ChannelId, guild: GuildId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn unmoo_message(&self, message: MessageId, channel: ChannelId, guild: GuildId, board: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let message = Decimal::from_u64(message.0).unwrap();
         let channel = Decimal::from_u64(channel.0).unwrap();
         let server = Decimal::from_u64(guild.0).unwrap();
 
         conn.query(
-            "DELETE FROM [Cowboard].[Message] WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P3",
-            &[&message, &channel, &server])
+            "DELETE FROM [Cowboard].[Message] WHERE message_id = @P1 AND message_channel_id = @P2 AND guild_id = @P3 AND board = @P4",
+            &[&message, &channel, &server, &board])
             .await?;
 
         Ok(())
     }
-}
\ No newline at end of file
+}
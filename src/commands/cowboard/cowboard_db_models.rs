@@ -1,23 +1,43 @@
+use chrono::NaiveDateTime;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use serenity::model::channel::ReactionType;
+
 pub struct Cowboard {
     pub id: u64,
+    pub name: String,
     pub channel: Option<u64>,
     pub add_threshold: i32,
     pub remove_threshold: i32,
     pub emote: String,
     pub webhook_id: Option<u64>,
-    pub webhook_token: Option<String>
+    pub webhook_token: Option<String>,
+    pub xp_bonus: i32,
+    pub forum_tags: Vec<u64>
 }
 
 impl Cowboard {
-    pub fn new(id: u64) -> Self {
+    pub fn new(id: u64, name: &str) -> Self {
         Cowboard {
             id,
+            name: name.to_string(),
             channel: None,
             add_threshold: 5,
             remove_threshold: 4,
             emote: "🐮".to_string(),
             webhook_id: None,
-            webhook_token: None
+            webhook_token: None,
+            xp_bonus: 0,
+            forum_tags: Vec::new()
+        }
+    }
+
+    // Custom emotes can be renamed, so only their IDs are compared.
+    pub fn matches(&self, emoji: &ReactionType) -> bool {
+        match (ReactionType::try_from(self.emote.as_str()), emoji) {
+            (Ok(ReactionType::Custom { id, .. }), ReactionType::Custom { id: other, .. }) => id == *other,
+            (Ok(ReactionType::Unicode(name)), ReactionType::Unicode(other)) => name == *other,
+            _ => false
         }
     }
 }
@@ -27,5 +47,96 @@ pub struct CowboardMessage {
     pub message_channel_id: u64,
     pub post_id: u64,
     pub post_channel_id: u64,
-    pub guild_id: u64
-}
\ No newline at end of file
+    pub guild_id: u64,
+    pub board: String,
+    pub xp_awarded: i32,
+    pub author_id: u64,
+    pub peak_reactions: i32
+}
+
+// A source channel or category that a board accepts (allow) or ignores (deny) messages from.
+pub struct CowboardFilter {
+    pub channel: u64,
+    pub allow: bool
+}
+
+// Server-wide rules for which messages and reactions count towards any board.
+pub struct CowboardRules {
+    pub id: u64,
+    pub count_self: bool,
+    pub allow_bots: bool,
+    pub min_account_age: i32,
+    pub required_role: Option<u64>,
+    pub max_message_age: i32,
+    pub keep_deleted: bool
+}
+
+impl CowboardRules {
+    pub fn new(id: u64) -> Self {
+        CowboardRules {
+            id,
+            count_self: false,
+            allow_bots: true,
+            min_account_age: 0,
+            required_role: None,
+            max_message_age: 0,
+            keep_deleted: false
+        }
+    }
+
+    // Anything beyond self-reactions needs every reacting user checked.
+    pub fn checks_reactors(&self) -> bool {
+        self.min_account_age > 0 || self.required_role.is_some()
+    }
+}
+
+// Board activity over some period, for `cowboard stats`.
+pub struct CowboardStats {
+    pub posts: i32,
+    pub reactions: i32,
+    pub top_users: Vec<(u64, i32)>,
+    pub top_channels: Vec<(u64, i32)>,
+    pub top_messages: Vec<CowboardMessage>
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum DigestPeriod {
+    Weekly = 0,
+    Monthly = 1
+}
+
+impl TryFrom<u8> for DigestPeriod {
+    type Error = ();
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        FromPrimitive::from_u8(v).ok_or(())
+    }
+}
+
+impl DigestPeriod {
+    pub fn days(&self) -> i32 {
+        match self {
+            DigestPeriod::Weekly => 7,
+            DigestPeriod::Monthly => 30
+        }
+    }
+}
+
+// A scheduled summary of the top posts; no channel means it's off.
+pub struct CowboardDigest {
+    pub id: u64,
+    pub channel: Option<u64>,
+    pub period: DigestPeriod,
+    pub last_sent: Option<NaiveDateTime>
+}
+
+impl CowboardDigest {
+    pub fn new(id: u64) -> Self {
+        CowboardDigest {
+            id,
+            channel: None,
+            period: DigestPeriod::Weekly,
+            last_sent: None
+        }
+    }
+}
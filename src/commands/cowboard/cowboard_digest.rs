@@ -0,0 +1,274 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tracing::{error, info};
+use serenity::{
+    CacheAndHttp,
+    builder::CreateEmbed,
+    model::id::{ChannelId, GuildId},
+    prelude::TypeMap
+};
+use tokio::sync::RwLock;
+use tokio::time;
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::commands::cowboard::cowboard_db_models::{CowboardDigest, DigestPeriod};
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("show", "channel", "period", "off", "preview"),
+    guild_only,
+    discard_spare_arguments,
+    description_localized("en-US", "Configure the scheduled summary of the top cowboard posts."),
+)]
+pub async fn digest(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+async fn update_digest(ctx: &CowContext<'_>, guild_id: GuildId, success: &str, modify: impl FnOnce(&mut CowboardDigest)) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    match db.get_cowboard_digest(guild_id).await {
+        Ok(mut digest) => {
+            modify(&mut digest);
+
+            if let Err(ex) = db.update_cowboard_digest(&digest).await {
+                ctx.say("We couldn't update the digest, sorry... Try again later?").await?;
+                error!("Failed to update cowboard digest: {}", ex);
+            } else {
+                ctx.say(success).await?;
+            }
+        }
+        Err(ex) => {
+            ctx.say("We couldn't get the digest settings... try again later?").await?;
+            error!("Failed to get cowboard digest: {}", ex);
+        }
+    }
+
+    Ok(())
+}
+
+fn period_name(period: DigestPeriod) -> &'static str {
+    match period {
+        DigestPeriod::Weekly => "Weekly",
+        DigestPeriod::Monthly => "Monthly"
+    }
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "Get the current settings for the cowboard digest."),
+    guild_only,
+    discard_spare_arguments
+)]
+pub async fn show(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Ok(digest) = db.get_cowboard_digest(guild_id).await {
+            ctx.send(|m| {
+                m.embeds.clear();
+                m.embed(|e|
+                    e
+                        .title("Cowboard Digest")
+                        .field("Channel", digest.channel.map(|o| format!("<#{o}>")).unwrap_or_else(|| "Disabled".to_string()), true)
+                        .field("Period", period_name(digest.period), true)
+                        .field("Last Sent", digest.last_sent.map(|o| format!("<t:{}:R>", o.timestamp())).unwrap_or_else(|| "Never".to_string()), true)
+                )
+            }).await?;
+        } else {
+            ctx.say("Failed to fetch the cowboard digest settings for this server...").await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set the channel the cowboard digest is posted in, turning it on."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn channel(
+    ctx: CowContext<'_>,
+    #[description = "The channel to post in; defaults to this one."] channel: Option<ChannelId>)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let channel = channel.unwrap_or_else(|| ctx.channel_id());
+
+        if !ctx.guild().map(|g| g.channels.contains_key(&channel)).unwrap_or(false) {
+            ctx.say("Could not find channel in this server!").await?;
+            return Ok(())
+        }
+
+        // Start counting from now, so the first digest covers a full period.
+        update_digest(&ctx, guild_id, &format!("The cowboard digest will be posted in <#{channel}>."), |d| {
+            if d.channel.is_none() {
+                d.last_sent = Some(Utc::now().naive_utc());
+            }
+            d.channel = Some(channel.0);
+        }).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set how often the cowboard digest is posted."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn period(
+    ctx: CowContext<'_>,
+    #[description = "Either weekly or monthly."] period: String)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let period = match period.to_lowercase().as_str() {
+            "week" | "weekly" => DigestPeriod::Weekly,
+            "month" | "monthly" => DigestPeriod::Monthly,
+            _ => {
+                ctx.say("The period must be either weekly or monthly.").await?;
+                return Ok(())
+            }
+        };
+
+        update_digest(&ctx, guild_id, &format!("The cowboard digest is now {}.", period_name(period).to_lowercase()), |d| d.period = period).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Stop posting the cowboard digest."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn off(ctx: CowContext<'_>) -> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        update_digest(&ctx, guild_id, "Disabled the cowboard digest.", |d| d.channel = None).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "See what the cowboard digest would look like right now."),
+    discard_spare_arguments
+)]
+pub async fn preview(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let digest = db.get_cowboard_digest(guild_id).await?;
+        match build_digest(&db, &digest).await {
+            Ok(Some(embed)) => {
+                ctx.send(|m| {
+                    m.embeds.clear();
+                    m.embeds.push(embed);
+                    m.allowed_mentions(|o| o.empty_users().empty_parse().empty_roles())
+                }).await?;
+            }
+            Ok(None) => {
+                ctx.say("Nothing has made it onto the cowboard in that time... yet!").await?;
+            }
+            Err(ex) => {
+                ctx.say("Failed to get cowboard stats.").await?;
+                error!("Failed to build cowboard digest: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+// None if nothing was posted during the period.
+async fn build_digest(db: &Database, digest: &CowboardDigest) -> Result<Option<CreateEmbed>, Error> {
+    let stats = db.get_cowboard_stats(GuildId::from(digest.id), None, digest.period.days()).await?;
+    if stats.posts == 0 {
+        return Ok(None);
+    }
+
+    let posts = stats.top_messages.iter()
+        .enumerate()
+        .map(|(i, o)| format!("{}. [{} reactions](https://discord.com/channels/{}/{}/{}) by <@{}> in `{}`", i + 1, o.peak_reactions, o.guild_id, o.message_channel_id, o.message_id, o.author_id, o.board))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let authors = stats.top_users.iter()
+        .enumerate()
+        .map(|(i, (id, posts))| format!("{}. <@{id}> - {posts} posts", i + 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("{} Cowboard Digest", period_name(digest.period)))
+        .description(format!("{} posts made it onto the cowboard, with {} reactions at their peak.", stats.posts, stats.reactions))
+        .field("Top Posts", if posts.is_empty() { "Nothing yet!".to_string() } else { posts }, false)
+        .field("Top Authors", if authors.is_empty() { "Nobody yet!".to_string() } else { authors }, false)
+        .timestamp(Utc::now());
+
+    Ok(Some(embed))
+}
+
+// Posts any digests that are due every hour.
+pub async fn check_digests(data: Arc<RwLock<TypeMap>>, ctx: Arc<CacheAndHttp>) {
+    let mut interval_hour = time::interval(Duration::from_secs(60 * 60));
+    loop {
+        interval_hour.tick().await;
+        let db = {
+            let ctx_global = data.read().await;
+            ctx_global.get::<Database>().expect("Couldn't find database").clone()
+        };
+
+        match db.get_due_digests().await {
+            Ok(digests) => {
+                for mut digest in digests {
+                    match build_digest(&db, &digest).await {
+                        Ok(Some(embed)) => {
+                            let channel = ChannelId::from(digest.channel.unwrap());
+                            match channel.send_message(&ctx.http, |m| m.set_embed(embed).allowed_mentions(|o| o.empty_users().empty_parse().empty_roles())).await {
+                                Ok(_) => info!("Sent cowboard digest for server {}", digest.id),
+                                Err(ex) => error!("Failed to send cowboard digest for server {}: {}", digest.id, ex)
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(ex) => error!("Failed to build cowboard digest for server {}: {}", digest.id, ex)
+                    }
+
+                    // Mark it as sent even if it failed, so we don't retry every tick.
+                    digest.last_sent = Some(Utc::now().naive_utc());
+                    if let Err(ex) = db.update_cowboard_digest(&digest).await {
+                        error!("Failed to record the cowboard digest: {}", ex);
+                    }
+                }
+            },
+            Err(ex) => {
+                error!("Failed to query due cowboard digests: {}", ex);
+            }
+        }
+    }
+}
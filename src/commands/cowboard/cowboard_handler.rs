@@ -1,152 +1,596 @@
-use std::path::Path;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::error;
-use tokio::fs::File;
 use tracing::error;
+use serenity::builder::CreateEmbed;
 use serenity::client::Context;
-use serenity::model::channel::{Embed, Message, Reaction, ReactionType, AttachmentType};
-use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
-use tokio::io::AsyncWriteExt;
+use serenity::json::Value;
+use serenity::model::channel::{Attachment, ChannelType, Embed, Message, Reaction, ReactionType, AttachmentType};
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
+use serenity::prelude::TypeMapKey;
+use chrono::Utc;
 use crate::{Database, db};
-use crate::commands::cowboard::cowboard_db_models::{Cowboard};
+use crate::commands::cowboard::cowboard_db_models::{Cowboard, CowboardMessage};
+use crate::commands::cowboard::cowboard_rest;
+use crate::services::message_handler::award_xp;
+use serde_json::json;
 
 async fn count_reactions(ctx: &Context, message: &Message, config: &Cowboard) -> Result<u64, Box<dyn error::Error + Send + Sync>>{
-    let config_emote = ReactionType::try_from(config.emote.as_str())?;
-    let matched_reaction = message.reactions.iter().find(|o|o.reaction_type.eq(&config_emote));
+    let matched_reaction = message.reactions.iter().find(|o| config.matches(&o.reaction_type));
     if let Some(reaction) = matched_reaction {
+        let db = db!(ctx);
+        let guild_id = GuildId::from(config.id);
+        let rules = db.get_cowboard_rules(guild_id).await?;
         let count = reaction.count;
-        let people = message.reaction_users(&ctx.http, config_emote, None, UserId::from(message.author.id.0 - 2)).await?;
-        if people.iter().any(|o| o.id == message.author.id) {
-            return Ok(count - 1);
+
+        if !rules.checks_reactors() {
+            if rules.count_self {
+                return Ok(count);
+            }
+
+            let people = message.reaction_users(&ctx.http, reaction.reaction_type.clone(), None, UserId::from(message.author.id.0 - 2)).await?;
+            if people.iter().any(|o| o.id == message.author.id) {
+                return Ok(count - 1);
+            }
+            return Ok(count);
         }
-        return Ok(count);
+
+        // Every reactor has to be looked at, 100 at a time; the full count is shown on the post and kept for stats.
+        let newest_account = Utc::now().timestamp() - rules.min_account_age as i64 * 86400;
+        let mut counted = 0;
+        let mut after = None;
+        loop {
+            let people = message.reaction_users(&ctx.http, reaction.reaction_type.clone(), Some(100), after).await?;
+            for person in &people {
+                if person.id == message.author.id && !rules.count_self {
+                    continue;
+                }
+
+                if rules.min_account_age > 0 && person.created_at().unix_timestamp() > newest_account {
+                    continue;
+                }
+
+                if let Some(role) = rules.required_role {
+                    let role = RoleId::from(role);
+                    let has_role = match ctx.cache.member(guild_id, person.id) {
+                        Some(member) => member.roles.contains(&role),
+                        None => guild_id.member(ctx, person.id).await
+                            .map(|m| m.roles.contains(&role))
+                            .unwrap_or(false)
+                    };
+                    if !has_role {
+                        continue;
+                    }
+                }
+
+                counted += 1;
+            }
+
+            if people.len() < 100 {
+                break;
+            }
+            after = people.last().map(|o| o.id);
+        }
+
+        return Ok(counted);
     }
 
     Ok(0)
 }
 
+// Whether a message can make it onto a board for the first time, going by the server's rules.
+// Backfills skip the age limit, since digging up old messages is the whole point.
+async fn message_eligible(ctx: &Context, guild_id: GuildId, message: &Message, check_age: bool) -> Result<bool, Box<dyn error::Error + Send + Sync>> {
+    let db = db!(ctx);
+    let rules = db.get_cowboard_rules(guild_id).await?;
+
+    if !rules.allow_bots && (message.author.bot || message.webhook_id.is_some()) {
+        return Ok(false);
+    }
+
+    if check_age && rules.max_message_age > 0 && Utc::now().timestamp() - message.timestamp.unix_timestamp() > rules.max_message_age as i64 * 3600 {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+// Finds the board in the server that the reaction is for, if any.
+async fn find_board(ctx: &Context, guild_id: GuildId, emoji: &ReactionType) -> Result<Option<Cowboard>, Box<dyn error::Error + Send + Sync>> {
+    let db = db!(ctx);
+    let boards = db.get_cowboards(guild_id).await?;
+
+    Ok(boards.into_iter().find(|o| o.matches(emoji)))
+}
+
+// Whether messages from this channel may be posted to the board, going by its filters and NSFW status.
+pub async fn source_allowed(ctx: &Context, config: &Cowboard, channel_id: ChannelId) -> Result<bool, Box<dyn error::Error + Send + Sync>> {
+    let db = db!(ctx);
+
+    // Threads are checked against their parent channel too, and channels against their category.
+    let mut scopes = vec![channel_id.0];
+    let mut nsfw = false;
+    let mut current = channel_id.to_channel(ctx).await?.guild();
+    while let Some(channel) = current {
+        nsfw |= channel.nsfw;
+        current = match channel.parent_id {
+            Some(parent) if channel.kind != ChannelType::Category => {
+                scopes.push(parent.0);
+                parent.to_channel(ctx).await?.guild()
+            }
+            _ => None
+        };
+    }
+
+    if nsfw {
+        let target_nsfw = match config.channel {
+            Some(target) => ChannelId::from(target).to_channel(ctx).await?.guild().map(|o| o.nsfw).unwrap_or(false),
+            None => false
+        };
+
+        if !target_nsfw {
+            return Ok(false);
+        }
+    }
+
+    let filters = db.get_cowboard_filters(GuildId::from(config.id), &config.name).await?;
+    if filters.iter().any(|o| !o.allow && scopes.contains(&o.channel)) {
+        return Ok(false);
+    }
+
+    let allowed = filters.iter().filter(|o| o.allow).collect::<Vec<_>>();
+    Ok(allowed.is_empty() || allowed.iter().any(|o| scopes.contains(&o.channel)))
+}
+
+// How long to wait for a burst of reactions to settle before recounting.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+// Messages with a recount underway; the flag marks that more reactions came in since it started.
+pub struct CowboardQueue;
+
+impl TypeMapKey for CowboardQueue {
+    type Value = Arc<Mutex<HashMap<(ChannelId, MessageId), bool>>>;
+}
+
 pub async fn add_reaction(ctx: &Context, added_reaction: &Reaction) {
-    if added_reaction.guild_id.is_none() {
+    reaction_changed(ctx, added_reaction).await;
+}
+
+pub async fn remove_reaction(ctx: &Context, removed_reaction: &Reaction) {
+    reaction_changed(ctx, removed_reaction).await;
+}
+
+async fn reaction_changed(ctx: &Context, reaction: &Reaction) {
+    if reaction.guild_id.is_none() {
         return;
     }
-    let guild_id = added_reaction.guild_id.unwrap();
+
+    let guild_id = reaction.guild_id.unwrap();
+    match find_board(ctx, guild_id, &reaction.emoji).await {
+        Ok(Some(_)) => {
+            enqueue(ctx, guild_id, reaction.channel_id, reaction.message_id).await;
+        }
+        Ok(None) => {}
+        Err(ex) => {
+            error!("Failed to get cowboard config: {}", ex);
+        }
+    }
+}
+
+// Only one task works on a message at a time; anyone arriving while it's busy just flags it to go again.
+async fn enqueue(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, message_id: MessageId) {
+    let queue = {
+        let ctx_global = ctx.data.read().await;
+        ctx_global.get::<CowboardQueue>().expect("Couldn't find cowboard queue").clone()
+    };
+    let key = (channel_id, message_id);
+
+    {
+        let mut pending = queue.lock().unwrap();
+        if let Some(dirty) = pending.get_mut(&key) {
+            *dirty = true;
+            return;
+        }
+        pending.insert(key, false);
+    }
+
+    loop {
+        tokio::time::sleep(DEBOUNCE).await;
+        queue.lock().unwrap().insert(key, false);
+
+        recount(ctx, guild_id, channel_id, message_id).await;
+
+        let mut pending = queue.lock().unwrap();
+        if pending.get(&key) != Some(&true) {
+            pending.remove(&key);
+            break;
+        }
+    }
+}
+
+// Brings every board up to date with the message's reactions: posting, editing, or taking it down.
+async fn recount(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, message_id: MessageId) {
     let db = db!(ctx);
-    match db.get_cowboard_config(guild_id).await {
-        Ok(mut config) => {
-            if config.channel.is_none() {
-                // No cowboard, why even check?
-                return;
+
+    let boards = match db.get_cowboards(guild_id).await {
+        Ok(boards) => boards,
+        Err(ex) => {
+            error!("Failed to get cowboards: {}", ex);
+            return;
+        }
+    };
+
+    let message = match channel_id.message(&ctx.http, message_id).await {
+        Ok(message) => message,
+        Err(ex) => {
+            error!("Failed to get reacted message: {}", ex);
+            return;
+        }
+    };
+
+    for mut config in boards {
+        if config.channel.is_none() {
+            // No cowboard, why even check?
+            continue;
+        }
+
+        let existing = match db.get_cowboard_message(message_id, channel_id, guild_id, &config.name).await {
+            Ok(existing) => existing,
+            Err(ex) => {
+                error!("Failed to get message from database: {}", ex);
+                continue;
             }
+        };
+
+        // The raw count is an upper bound, so most boards can be skipped without asking Discord who reacted.
+        let raw_count = message.reactions.iter()
+            .find(|o| config.matches(&o.reaction_type))
+            .map(|o| o.count)
+            .unwrap_or(0);
+        if existing.is_none() && raw_count < config.add_threshold as u64 {
+            continue;
+        }
 
-            match added_reaction.message(&ctx.http).await {
-                Ok(message) => {
-                    match count_reactions(ctx, &message, &config).await {
-                        Ok(count) => {
-                            // Pray that the database's constraints work.
-                            if count >= config.add_threshold as u64 {
-                                let post_message = db.get_cowboard_message(message.id, message.channel_id, guild_id).await;
-                                if let Ok(Some(post)) = post_message {
-                                    match ctx.http.get_message(post.post_channel_id, post.post_id).await {
-                                        Ok(mut post) => {
-                                            update_moo(ctx, &message, &mut post, &mut config).await;
-                                        }
-                                        Err(ex) => {
-                                            error!("Failed to get old cowboard message: {}", ex);
-                                            // Create a new copy
-                                            add_moo(ctx, guild_id, added_reaction, &message, &mut config).await;
-                                        }
-                                    }
-                                } else if let Err(ex) = post_message {
-                                    error!("Failed to get message from database: {}", ex);
-                                } else {
-                                    // Moo that thing!
-                                    add_moo(ctx, guild_id, added_reaction, &message, &mut config).await;
-                                }
-                            }
-                        }
-                        Err(ex) => {
-                            error!("Failed to count reactions: {}", ex);
-                        }
+        let count = match count_reactions(ctx, &message, &config).await {
+            Ok(count) => count,
+            Err(ex) => {
+                error!("Failed to count reactions: {}", ex);
+                continue;
+            }
+        };
+
+        if let Some(post) = existing {
+            if count < config.remove_threshold as u64 {
+                // Unmoo that thing!
+                remove_moo(ctx, guild_id, channel_id, message_id, &config).await;
+                continue;
+            }
+
+            if let Err(ex) = db.record_cowboard_peak(message_id, channel_id, guild_id, &config.name, count as i32).await {
+                error!("Failed to record the peak reaction count: {}", ex);
+            }
+
+            match ctx.http.get_message(post.post_channel_id, post.post_id).await {
+                Ok(mut post) => {
+                    update_moo(ctx, &message, &mut post, &mut config, count).await;
+                }
+                Err(ex) => {
+                    error!("Failed to get old cowboard message: {}", ex);
+                    // Create a new copy, replacing the old record
+                    if let Err(ex) = db.unmoo_message(message_id, channel_id, guild_id, &config.name).await {
+                        error!("Failed to unmoo a message in the database: {}", ex);
+                        continue;
                     }
+                    add_moo(ctx, guild_id, &message, &mut config, count).await;
                 }
+            }
+        } else if count >= config.add_threshold as u64 {
+            match source_allowed(ctx, &config, channel_id).await {
+                Ok(true) => {}
+                Ok(false) => continue,
                 Err(ex) => {
-                    error!("Failed to get reacted message: {}", ex);
+                    error!("Failed to check the cowboard's channel filters: {}", ex);
+                    continue;
+                }
+            }
+
+            match message_eligible(ctx, guild_id, &message, true).await {
+                Ok(true) => {
+                    // Moo that thing!
+                    add_moo(ctx, guild_id, &message, &mut config, count).await;
+                }
+                Ok(false) => {}
+                Err(ex) => {
+                    error!("Failed to check the cowboard rules: {}", ex);
                 }
             }
         }
-        Err(ex) => {
-            error!("Failed to get cowboard config: {}", ex);
-        }
     }
 }
 
-async fn add_moo(ctx: &Context, guild_id: GuildId, reaction: &Reaction, message: &Message, config: &mut Cowboard) {
+// Posts an old message found while backfilling if it qualifies, returning whether it was posted.
+pub async fn backfill_message(ctx: &Context, message: &Message, config: &mut Cowboard) -> Result<bool, Box<dyn error::Error + Send + Sync>> {
+    let db = db!(ctx);
+    let guild_id = GuildId::from(config.id);
+
+    if !message.reactions.iter().any(|o| config.matches(&o.reaction_type)) {
+        return Ok(false);
+    }
+
+    let count = count_reactions(ctx, message, config).await?;
+    if count < config.add_threshold as u64 {
+        return Ok(false);
+    }
+
+    if db.get_cowboard_message(message.id, message.channel_id, guild_id, &config.name).await?.is_some() {
+        return Ok(false);
+    }
+
+    if !message_eligible(ctx, guild_id, message, false).await? {
+        return Ok(false);
+    }
+
+    Ok(add_moo(ctx, guild_id, message, config, count).await)
+}
+
+// Returns whether the message ended up posted and recorded.
+async fn add_moo(ctx: &Context, guild_id: GuildId, message: &Message, config: &mut Cowboard, reacts: u64) -> bool {
     let db = db!(ctx);
 
     let message_result = if config.webhook_id.is_some() && config.webhook_token.is_some() {
-        send_webhook_message(ctx, message, config).await
+        send_webhook_message(ctx, message, config, reacts).await
     } else {
-        send_bot_message(ctx, message, config).await
+        send_bot_message(ctx, message, config, reacts).await
     };
 
     if let Err(ex) = message_result {
         error!("Failed to send cowboard message: {}", ex);
-        return;
+        return false;
     }
 
     let post_message = message_result.unwrap();
 
-    if let Err(ex) = db.moo_message(message.id, reaction.channel_id, post_message.id, post_message.channel_id, guild_id).await {
-        error!("Failed to moo a message in the database: {}", ex);
+    // Claim the message before handing out experience, in case something else got there first.
+    let record = CowboardMessage {
+        message_id: message.id.0,
+        message_channel_id: message.channel_id.0,
+        post_id: post_message.id.0,
+        post_channel_id: post_message.channel_id.0,
+        guild_id: guild_id.0,
+        board: config.name.clone(),
+        xp_awarded: 0,
+        author_id: message.author.id.0,
+        peak_reactions: reacts as i32
+    };
+
+    match db.moo_message(&record).await {
+        Ok(true) => {}
+        Ok(false) => {
+            if let Err(ex) = delete_post(ctx, post_message.channel_id.0, post_message.id.0).await {
+                error!("Failed to delete duplicate cowboard message: {}", ex);
+            }
+            return false;
+        }
+        Err(ex) => {
+            // Without a record nothing would ever update or remove the post, so take it back down.
+            error!("Failed to moo a message in the database: {}", ex);
+            if let Err(ex) = delete_post(ctx, post_message.channel_id.0, post_message.id.0).await {
+                error!("Failed to delete unrecorded cowboard message: {}", ex);
+            }
+            return false;
+        }
     }
+
+    let xp_awarded = award_moo(ctx, guild_id, message, config).await;
+    if xp_awarded > 0 {
+        if let Err(ex) = db.set_cowboard_xp_awarded(message.id, message.channel_id, guild_id, &config.name, xp_awarded).await {
+            error!("Failed to record cowboard bonus experience: {}", ex);
+        }
+    }
+
+    true
 }
 
-async fn update_moo(ctx: &Context, message: &Message, post_message: &mut Message, config: &mut Cowboard) {
+// Gives the author the cowboard's bonus experience, returning how much was actually awarded.
+async fn award_moo(ctx: &Context, guild_id: GuildId, message: &Message, config: &Cowboard) -> i32 {
+    if config.xp_bonus <= 0 || message.author.bot {
+        return 0;
+    }
+
+    let db = db!(ctx);
+    let roles = guild_id.member(ctx, message.author.id).await.map(|m| m.roles).unwrap_or_default();
+    match db.get_exclusions(guild_id).await {
+        Ok(exclusions) if exclusions.excludes(message.author.id, &roles) => return 0,
+        Err(ex) => error!("Failed checking if the author was excluded: {}", ex),
+        _ => {}
+    }
+
+    match award_xp(ctx, guild_id, message.channel_id, &message.author, config.xp_bonus).await {
+        Ok(_) => config.xp_bonus,
+        Err(ex) => {
+            error!("Failed to award cowboard bonus experience: {}", ex);
+            0
+        }
+    }
+}
+
+async fn update_moo(ctx: &Context, message: &Message, post_message: &mut Message, config: &mut Cowboard, reacts: u64) {
     if config.webhook_id.is_some() && config.webhook_token.is_some() {
-        update_webhook_message(ctx, message, post_message, config).await
+        update_webhook_message(ctx, message, post_message, config, reacts).await
     } else {
-        update_bot_message(ctx, message, post_message, config).await
+        update_bot_message(ctx, message, post_message, config, reacts).await
     };
 }
 
-async fn send_bot_message(ctx: &Context, message: &Message, config: &Cowboard) -> Result<Message, Box<dyn error::Error + Send + Sync>> {
-    let channel = ChannelId::from(config.channel.unwrap());
+const GALLERY_SIZE: usize = 4;
+
+// Images for the gallery, and links for anything that wasn't uploaded and can't be shown inline.
+struct PostMedia {
+    images: Vec<String>,
+    links: Vec<String>
+}
+
+fn is_image(item: &Attachment) -> bool {
+    match &item.content_type {
+        Some(kind) => kind.starts_with("image/"),
+        None => item.dimensions().is_some()
+    }
+}
+
+fn is_playable(item: &Attachment) -> bool {
+    item.content_type.as_ref().map(|o| o.starts_with("video/") || o.starts_with("audio/")).unwrap_or(false)
+}
+
+// Works out what the post shows, given which of the message's attachments were uploaded alongside it.
+fn collect_media(message: &Message, uploaded: &[String]) -> PostMedia {
+    let mut images = Vec::new();
+    let mut links = Vec::new();
+
+    for item in &message.attachments {
+        let was_uploaded = uploaded.contains(&item.filename);
+        if is_image(item) && images.len() < GALLERY_SIZE {
+            images.push(if was_uploaded { format!("attachment://{}", item.filename) } else { item.url.clone() });
+        } else if !was_uploaded {
+            links.push(format!("[{}]({})", item.filename, item.url));
+        }
+    }
+
+    // Lottie stickers are JSON animations, which embeds can't show.
+    for sticker in &message.sticker_items {
+        match sticker.image_url().filter(|o| !o.ends_with(".json")) {
+            Some(url) if images.len() < GALLERY_SIZE => images.push(url),
+            Some(url) => links.push(format!("[{}]({})", sticker.name, url)),
+            None => links.push(format!("Sticker: {}", sticker.name))
+        }
+    }
+
+    PostMedia {
+        images,
+        links
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(max - 3).collect::<String>())
+    }
+}
+
+// Embeds sharing a URL are shown as one gallery, so every image after the first gets its own embed.
+async fn render_embeds(ctx: &Context, message: &Message, link: &str, media: &PostMedia) -> Vec<CreateEmbed> {
     let output_username = format_username(ctx, message).await;
     let safe_content = message.content_safe(ctx);
 
-    let attachments = download_image_attachments(message).await;
+    let mut main = CreateEmbed::default();
+    main
+        .author(|a|
+            a.name(&output_username).icon_url(message.author.face()))
+        .description(&safe_content)
+        .url(link)
+        .timestamp(message.timestamp)
+        .footer(|f| f.text(format!("Message ID: {} / User ID: {}", message.id, message.author.id)));
+
+    if let Some(reply) = &message.referenced_message {
+        let reply_link = reply.link_ensured(&ctx.http).await;
+        let snippet = truncate(&reply.content_safe(ctx), 200);
+        main.field(format!("Replying to {}", reply.author.name), format!("{snippet}\n[Jump to message]({reply_link})"), false);
+    }
+
+    if let Some(embed) = message.embeds.first() {
+        if embed.title.is_some() || embed.description.is_some() {
+            let title = truncate(embed.title.as_deref().unwrap_or("Embed"), 256);
+            let description = embed.description.as_deref().or(embed.url.as_deref()).unwrap_or("\u{200b}");
+            main.field(title, truncate(description, 1024), false);
+        }
+    }
+
+    if !media.links.is_empty() {
+        main.field("Attachments", truncate(&media.links.join("\n"), 1024), false);
+    }
+
+    let mut embeds = vec![main];
+    for (i, image) in media.images.iter().enumerate() {
+        if i == 0 {
+            embeds[0].image(image);
+        } else {
+            let mut extra = CreateEmbed::default();
+            extra.url(link).image(image);
+            embeds.push(extra);
+        }
+    }
+
+    embeds
+}
+
+// Where a board posts; forums get a new post per message, and threads need webhooks pointed at them.
+#[derive(PartialEq, Eq)]
+enum Target {
+    Channel,
+    Thread,
+    Forum
+}
+
+async fn target_of(ctx: &Context, config: &Cowboard) -> Result<Target, Box<dyn error::Error + Send + Sync>> {
+    let channel = ChannelId::from(config.channel.unwrap()).to_channel(ctx).await?;
+    Ok(match channel.guild().map(|o| o.kind) {
+        Some(ChannelType::Forum) => Target::Forum,
+        Some(ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread) => Target::Thread,
+        _ => Target::Channel
+    })
+}
+
+fn forum_title(ctx: &Context, message: &Message) -> String {
+    let content = message.content_safe(ctx);
+    if content.is_empty() {
+        format!("{}'s message", message.author.name)
+    } else {
+        truncate(&format!("{}: {}", message.author.name, content), 100)
+    }
+}
+
+async fn send_bot_message(ctx: &Context, message: &Message, config: &Cowboard, reacts: u64) -> Result<Message, Box<dyn error::Error + Send + Sync>> {
+    let channel = ChannelId::from(config.channel.unwrap());
+
+    // Forum posts go through as JSON, so media is linked rather than uploaded.
+    if target_of(ctx, config).await? == Target::Forum {
+        let link = message.link_ensured(&ctx.http).await;
+        let embeds = to_values(render_embeds(ctx, message, &link, &collect_media(message, &[])).await);
+        let body = json!({
+            "name": forum_title(ctx, message),
+            "applied_tags": config.forum_tags.iter().map(|o| o.to_string()).collect::<Vec<_>>(),
+            "message": {
+                "content": format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link),
+                "embeds": embeds
+            }
+        });
+
+        return cowboard_rest::create_forum_post(&ctx.http, channel.0, &body).await;
+    }
+
+    let attachments = download_attachments(message).await;
+    let uploaded = attachments.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
 
-    let reacts = count_reactions(ctx, message, config).await?;
     let link = message.link_ensured(&ctx.http).await;
+    let embeds = render_embeds(ctx, message, &link, &collect_media(message, &uploaded)).await;
 
     let message_output = channel.send_message(&ctx.http, |m|
         {
             let execution = m
                 .content(format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link))
-                .embed(|e| {
-                    let temp = e
-                        .author(|a|
-                            a.name(&output_username).icon_url(message.author.face()))
-                        .description(&safe_content)
-                        .timestamp(message.timestamp)
-                        .footer(|f| f.text(format!("Message ID: {} / User ID: {}", message.id, message.author.id)));
-
-                    if !attachments.is_empty() {
-                        let (name, _) = &attachments[0];
-                        temp.attachment(name);
-                    }
-
-                    temp
-                });
+                .set_embeds(embeds);
 
-            for (_, path) in &attachments {
-                execution.add_file(AttachmentType::Path(Path::new(path)));
+            for (name, data) in &attachments {
+                execution.add_file(AttachmentType::Bytes { data: Cow::Borrowed(data), filename: name.clone() });
             }
 
             execution
         }
     ).await;
 
-    delete_image_attachments(message).await;
     match message_output {
         Ok(message) => {
             Ok(message)
@@ -157,129 +601,117 @@ async fn send_bot_message(ctx: &Context, message: &Message, config: &Cowboard) -
     }
 }
 
-async fn update_bot_message(ctx: &Context, message: &Message, post_message: &mut Message, config: &Cowboard) {
-    match count_reactions(ctx, message, config).await {
-        Ok(reacts) => {
-            let link = message.link_ensured(&ctx.http).await;
-            if let Err(ex) = post_message.edit(&ctx.http,
-                                                  |m| m.content(format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link))).await {
-                error!("Failed to edit post message??? {}", ex);
-            }
-        }
-        Err(ex) => {
-            error!("Failed to count reactions: {}", ex);
+// Re-renders the whole post, so edits to the original show up too; uploaded files are kept as they were.
+async fn update_bot_message(ctx: &Context, message: &Message, post_message: &mut Message, config: &Cowboard, reacts: u64) {
+    let uploaded = post_message.attachments.iter().map(|o| o.filename.clone()).collect::<Vec<_>>();
+    let link = message.link_ensured(&ctx.http).await;
+    let embeds = render_embeds(ctx, message, &link, &collect_media(message, &uploaded)).await;
+
+    if let Err(ex) = post_message.edit(&ctx.http, |m| m
+        .content(format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link))
+        .set_embeds(embeds)).await {
+        error!("Failed to edit post message??? {}", ex);
+    }
+}
+
+// Webhooks take embeds as raw JSON.
+fn to_values(embeds: Vec<CreateEmbed>) -> Vec<Value> {
+    embeds.into_iter().map(|embed| Embed::fake(|e| {
+        *e = embed;
+        e
+    })).collect()
+}
+
+// Falls back to posting as the bot if the webhook fails, and forgets the webhook only if Discord says it's gone.
+async fn send_webhook_message(ctx: &Context, message: &Message, config: &mut Cowboard, reacts: u64) -> Result<Message, Box<dyn error::Error + Send + Sync>> {
+    match execute_cowboard_webhook(ctx, message, config, reacts).await {
+        Ok(webhook_message) => return Ok(webhook_message),
+        Err(ex) if cowboard_rest::webhook_gone(&ex) => {
+            error!("The cowboard webhook is gone, posting as the bot from now on: {}", ex);
+            disable_webhook(ctx, config).await;
         }
+        Err(ex) => error!("Failed to send webhook message, posting as the bot instead: {}", ex)
     }
+
+    send_bot_message(ctx, message, config, reacts).await
 }
 
-async fn send_webhook_message(ctx: &Context, message: &Message, config: &mut Cowboard) -> Result<Message, Box<dyn error::Error + Send + Sync>> {
+async fn execute_cowboard_webhook(ctx: &Context, message: &Message, config: &Cowboard, reacts: u64) -> Result<Message, Box<dyn error::Error + Send + Sync>> {
     let token = config.webhook_token.clone().unwrap();
-    if let Ok(webhook) = ctx.http.get_webhook_with_token(config.webhook_id.unwrap(), &token).await {
-        let output_username = format_username(ctx, message).await;
-        let safe_content = message.content_safe(ctx);
-
-        let attachments = download_image_attachments(message).await;
-
-        let embeds = vec![
-            Embed::fake(|e|
-                {
-                    let temp = e
-                        .author(|a|
-                            a.name(&output_username).icon_url(message.author.face()))
-                        .description(&safe_content)
-                        .timestamp(message.timestamp)
-                        .footer(|f| f.text(format!("Message ID: {} / User ID: {}", message.id, message.author.id)));
-
-                    if !attachments.is_empty() {
-                        let (name, _) = &attachments[0];
-                        temp.attachment(name);
-                    }
-
-                    temp
-                }
-            )
-        ];
+    let webhook = ctx.http.get_webhook_with_token(config.webhook_id.unwrap(), &token).await?;
+    let output_username = format_username(ctx, message).await;
+    let target = target_of(ctx, config).await?;
 
-        let reacts = count_reactions(ctx, message, config).await?;
+    // Threads and forums go through as JSON, so media is linked rather than uploaded.
+    if target != Target::Channel {
         let link = message.link_ensured(&ctx.http).await;
-        if let Ok(Some(webhook_message)) = webhook.execute(&ctx.http, true, |m|
-            {
-                let execution = m
-                    .content(format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link))
-                    .embeds(embeds)
-                    .avatar_url(message.author.face())
-                    .username(output_username);
-
-                for (_, path) in &attachments {
-                    execution.add_file(AttachmentType::Path(Path::new(path)));
-                }
-
-                execution
-            }
-        ).await {
-            delete_image_attachments(message).await;
-            return Ok(webhook_message);
-        }
+        let embeds = to_values(render_embeds(ctx, message, &link, &collect_media(message, &[])).await);
+        let mut body = json!({
+            "content": format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link),
+            "embeds": embeds,
+            "avatar_url": message.author.face(),
+            "username": output_username
+        });
+
+        let thread_id = if target == Target::Forum {
+            body["thread_name"] = json!(forum_title(ctx, message));
+            body["applied_tags"] = json!(config.forum_tags.iter().map(|o| o.to_string()).collect::<Vec<_>>());
+            None
+        } else {
+            config.channel
+        };
+
+        return cowboard_rest::execute_webhook(webhook.id.0, &token, thread_id, &body).await;
     }
 
-    delete_image_attachments(message).await;
-    disable_webhook(ctx, config).await;
-    send_bot_message(ctx, message, config).await
-}
+    let attachments = download_attachments(message).await;
+    let uploaded = attachments.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+
+    let link = message.link_ensured(&ctx.http).await;
+    let embeds = to_values(render_embeds(ctx, message, &link, &collect_media(message, &uploaded)).await);
 
-async fn download_image_attachments(message: &Message) -> Vec<(String, String)> {
-    let mut out: Vec<(String, String)> = Vec::new();
+    let webhook_message = webhook.execute(&ctx.http, true, |m|
+        {
+            let execution = m
+                .content(format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link))
+                .embeds(embeds)
+                .avatar_url(message.author.face())
+                .username(output_username);
 
-    let directory = format!("cowboard/{}", message.id);
+            for (name, data) in &attachments {
+                execution.add_file(AttachmentType::Bytes { data: Cow::Borrowed(data), filename: name.clone() });
+            }
 
-    if let Err(ex) = tokio::fs::create_dir_all(&directory).await {
-        error!("Failed to create temporary directory: {}", ex);
-        return out;
-    }
+            execution
+        }
+    ).await?;
 
+    Ok(webhook_message.ok_or("The webhook didn't send back its message")?)
+}
+
+// Images, video, and audio are uploaded while they fit in the size limit; anything else is linked by URL.
+// Everything stays in memory, so boards posting the same message at once can't trip over each other.
+async fn download_attachments(message: &Message) -> Vec<(String, Vec<u8>)> {
+    let mut out: Vec<(String, Vec<u8>)> = Vec::new();
     let mut size_limit: u64 = 8 * 1024 * 1024;
 
     for item in message.attachments.iter() {
-        if item.dimensions().is_some() && size_limit >= item.size {
-            // Is an image that we can upload!
-            let content = match item.download().await {
-                Ok(content) => content,
-                Err(ex) => {
-                    error!("Error downloading file: {}", ex);
-                    continue;
+        if (is_image(item) || is_playable(item)) && size_limit >= item.size {
+            match item.download().await {
+                Ok(content) => {
+                    size_limit -= item.size;
+                    out.push((item.filename.clone(), content));
                 }
-            };
-
-            let file_path = format!("{}/{}", &directory, &item.filename);
-            let mut file = match File::create(&file_path).await {
-                Ok(file) => file,
                 Err(ex) => {
-                    error!("Error creating file: {}", ex);
-                    continue;
+                    error!("Error downloading file: {}", ex);
                 }
-            };
-
-            if let Err(ex) = file.write_all(&content).await {
-                error!("Error saving image: {}", ex);
-                continue;
             }
-
-            size_limit -= item.size;
-            out.push((item.filename.clone(), file_path));
         }
     }
 
     out
 }
 
-async fn delete_image_attachments(message: &Message) {
-    let directory = format!("cowboard/{}", message.id);
-
-    if let Err(ex) = tokio::fs::remove_dir_all(&directory).await {
-        error!("Failed to remove directory: {}", ex);
-    }
-}
-
 async fn format_username(ctx: &Context, message: &Message) -> String {
     let username = format!("{}#{:04}", message.author.name, message.author.discriminator);
     let nickname = message.author_nick(&ctx.http).await;
@@ -291,23 +723,38 @@ async fn format_username(ctx: &Context, message: &Message) -> String {
     }
 }
 
-async fn update_webhook_message(ctx: &Context, message: &Message, post_message: &Message, config: &mut Cowboard) {
+async fn update_webhook_message(ctx: &Context, message: &Message, post_message: &Message, config: &mut Cowboard, reacts: u64) {
     let token = config.webhook_token.clone().unwrap();
-    if let Ok(webhook) = ctx.http.get_webhook_with_token(config.webhook_id.unwrap(), &token).await {
-        match count_reactions(ctx, message, config).await {
-            Ok(reacts) => {
-                let link = message.link_ensured(&ctx.http).await;
-                if let Err(ex) = webhook.edit_message(&ctx.http, post_message.id,
-                                                      |m| m.content(format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link))).await {
-                    error!("Failed to edit post message??? {}", ex);
-                }
-            }
-            Err(ex) => {
-                error!("Failed to count reactions: {}", ex);
+    let webhook = match ctx.http.get_webhook_with_token(config.webhook_id.unwrap(), &token).await {
+        Ok(webhook) => webhook,
+        Err(ex) => {
+            let ex: Box<dyn error::Error + Send + Sync> = ex.into();
+            error!("Failed to get the cowboard webhook: {}", ex);
+            if cowboard_rest::webhook_gone(&ex) {
+                disable_webhook(ctx, config).await;
             }
+            return;
         }
+    };
+
+    let uploaded = post_message.attachments.iter().map(|o| o.filename.clone()).collect::<Vec<_>>();
+    let link = message.link_ensured(&ctx.http).await;
+    let content = format!("{} {} | <#{}>\n{}", reacts, &config.emote, message.channel_id, link);
+    let embeds = to_values(render_embeds(ctx, message, &link, &collect_media(message, &uploaded)).await);
+
+    // Posts in a thread or forum live somewhere other than the webhook's own channel.
+    let in_thread = target_of(ctx, config).await.map(|o| o != Target::Channel).unwrap_or(false);
+    let result = if in_thread {
+        let body = json!({ "content": content, "embeds": embeds });
+        cowboard_rest::edit_webhook_message(webhook.id.0, &token, post_message.channel_id.0, post_message.id.0, &body).await
     } else {
-        disable_webhook(ctx, config).await;
+        webhook.edit_message(&ctx.http, post_message.id, |m| m.content(content).embeds(embeds)).await
+            .map(|_| ())
+            .map_err(|o| o.into())
+    };
+
+    if let Err(ex) = result {
+        error!("Failed to edit post message??? {}", ex);
     }
 }
 
@@ -321,70 +768,175 @@ async fn disable_webhook(ctx: &Context, config: &mut Cowboard) {
     }
 }
 
-pub async fn remove_reaction(ctx: &Context, removed_reaction: &Reaction) {
-    if removed_reaction.guild_id.is_none() {
-        return;
+pub async fn reaction_remove_all(ctx: &Context, channel_id: ChannelId, message: MessageId) {
+    let guild_id = channel_id.message(&ctx.http, message).await.ok().and_then(|o| o.guild_id);
+    if let Some(guild) = guild_id {
+        let db = db!(ctx);
+        match db.get_cowboards(guild).await {
+            Ok(boards) => {
+                for board in boards {
+                    remove_moo(ctx, guild, channel_id, message, &board).await;
+                }
+            }
+            Err(ex) => {
+                error!("Failed to get cowboards: {}", ex);
+            }
+        }
+    }
+}
+
+// Forum posts share an ID with their first message, and are deleted as a whole.
+async fn delete_post(ctx: &Context, channel_id: u64, post_id: u64) -> serenity::Result<()> {
+    if channel_id == post_id {
+        ctx.http.delete_channel(channel_id).await.map(|_| ())
+    } else {
+        ctx.http.delete_message(channel_id, post_id).await
     }
+}
 
-    let guild_id = removed_reaction.guild_id.unwrap();
+async fn remove_moo(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, message: MessageId, config: &Cowboard) {
     let db = db!(ctx);
-    match db.get_cowboard_config(guild_id).await {
-        Ok(mut config) => {
-            match removed_reaction.message(&ctx.http).await {
-                Ok(message) => {
-                    match count_reactions(ctx, &message, &config).await {
-                        Ok(count) => {
-                            let post_message = db.get_cowboard_message(message.id, message.channel_id, guild_id).await;
-                            // Pray that the database's constraints work.
-                            if count < config.remove_threshold as u64 {
-                                // Unmoo that thing!
-                                remove_moo(ctx, guild_id, removed_reaction.channel_id, removed_reaction.message_id).await;
-                            } else if let Ok(Some(post)) = post_message {
-                                if let Ok(mut post) = ctx.http.get_message(post.post_channel_id, post.post_id).await {
-                                    update_moo(ctx, &message, &mut post, &mut config).await;
-                                }
+
+    match db.get_cowboard_message(message, channel_id, guild_id, &config.name).await {
+        Ok(message_info) => {
+            if let Some(cowboard_message) = message_info {
+                if let Err(ex) = delete_post(ctx, cowboard_message.post_channel_id, cowboard_message.post_id).await {
+                    error!("Failed to delete message: {} {} {}", ex, cowboard_message.post_channel_id, cowboard_message.post_id);
+                }
+
+                if cowboard_message.xp_awarded > 0 {
+                    // Newer records know the author, which still works once the original is deleted.
+                    let author = if cowboard_message.author_id > 0 {
+                        UserId::from(cowboard_message.author_id).to_user(ctx).await
+                    } else {
+                        channel_id.message(&ctx.http, message).await.map(|o| o.author)
+                    };
+
+                    match author {
+                        Ok(author) => {
+                            if let Err(ex) = award_xp(ctx, guild_id, channel_id, &author, -cowboard_message.xp_awarded).await {
+                                error!("Failed to revoke cowboard bonus experience: {}", ex);
                             }
                         }
                         Err(ex) => {
-                            error!("Failed to count reactions: {}", ex);
+                            error!("Failed to get the original author to revoke bonus experience: {}", ex);
                         }
                     }
                 }
-                Err(ex) => {
-                    error!("Failed to get reacted message: {}", ex);
-                }
             }
         }
         Err(ex) => {
-            error!("Failed to get cowboard config: {}", ex);
+            error!("Failed to query cowboard message: {}", ex);
         }
     }
-}
 
-pub async fn reaction_remove_all(ctx: &Context, channel_id: ChannelId, message: MessageId) {
-    let guild_id = channel_id.message(&ctx.http, message).await.ok().and_then(|o| o.guild_id);
-    if let Some(guild) = guild_id {
-        remove_moo(ctx, guild, channel_id, message).await;
+    if let Err(ex) = db.unmoo_message(message, channel_id, guild_id, &config.name).await {
+        error!("Failed to unmoo a message in the database: {}", ex);
     }
 }
 
-async fn remove_moo(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, message: MessageId) {
+pub async fn message_update(ctx: &Context, channel_id: ChannelId, message_id: MessageId, guild_id: Option<GuildId>) {
+    if guild_id.is_none() {
+        return;
+    }
+
+    let guild_id = guild_id.unwrap();
     let db = db!(ctx);
 
-    match db.get_cowboard_message(message, channel_id, guild_id).await {
-        Ok(message_info) => {
-            if let Some(cowboard_message) = message_info {
-                if let Err(ex) = ctx.http.delete_message(cowboard_message.post_channel_id, cowboard_message.post_id).await {
-                    error!("Failed to delete message: {} {} {}", ex, cowboard_message.post_channel_id, cowboard_message.post_id);
+    // This runs on every edit and link unfurl, so look the message up once across all boards before anything else.
+    let posts = match db.get_cowboard_posts(message_id, channel_id, guild_id).await {
+        Ok(posts) => posts,
+        Err(ex) => {
+            error!("Failed to get message from database: {}", ex);
+            return;
+        }
+    };
+
+    if posts.is_empty() {
+        return;
+    }
+
+    let boards = match db.get_cowboards(guild_id).await {
+        Ok(boards) => boards,
+        Err(ex) => {
+            error!("Failed to get cowboards: {}", ex);
+            return;
+        }
+    };
+
+    let posted: Vec<_> = boards.into_iter()
+        .filter_map(|board| posts.iter().find(|o| o.board == board.name).map(|post| (board, post)))
+        .collect();
+
+    match channel_id.message(&ctx.http, message_id).await {
+        Ok(message) => {
+            for (mut config, post) in posted {
+                let count = match count_reactions(ctx, &message, &config).await {
+                    Ok(count) => count,
+                    Err(ex) => {
+                        error!("Failed to count reactions: {}", ex);
+                        continue;
+                    }
+                };
+
+                match ctx.http.get_message(post.post_channel_id, post.post_id).await {
+                    Ok(mut post) => {
+                        update_moo(ctx, &message, &mut post, &mut config, count).await;
+                    }
+                    Err(ex) => {
+                        error!("Failed to get old cowboard message: {}", ex);
+                    }
                 }
             }
         }
         Err(ex) => {
-            error!("Failed to query cowboard message: {}", ex);
+            error!("Failed to get edited message: {}", ex);
         }
     }
+}
 
-    if let Err(ex) = db.unmoo_message(message, channel_id, guild_id).await {
-        error!("Failed to unmoo a message in the database: {}", ex);
+// Handles single and bulk deletions; posts are taken down unless the server wants to keep them.
+pub async fn message_delete(ctx: &Context, channel_id: ChannelId, messages: &[MessageId], guild_id: Option<GuildId>) {
+    if guild_id.is_none() {
+        return;
     }
-}
\ No newline at end of file
+
+    let guild_id = guild_id.unwrap();
+    let db = db!(ctx);
+
+    // Most deleted messages were never posted, so look them up before anything else, like message_update does.
+    let mut posted = Vec::new();
+    for message in messages {
+        match db.get_cowboard_posts(*message, channel_id, guild_id).await {
+            Ok(posts) if !posts.is_empty() => posted.push((*message, posts)),
+            Ok(_) => {}
+            Err(ex) => error!("Failed to get message from database: {}", ex)
+        }
+    }
+
+    if posted.is_empty() {
+        return;
+    }
+
+    match db.get_cowboard_rules(guild_id).await {
+        Ok(rules) if rules.keep_deleted => return,
+        Ok(_) => {}
+        Err(ex) => {
+            error!("Failed to get cowboard rules: {}", ex);
+            return;
+        }
+    }
+
+    match db.get_cowboards(guild_id).await {
+        Ok(boards) => {
+            for (message, posts) in &posted {
+                for board in boards.iter().filter(|board| posts.iter().any(|o| o.board == board.name)) {
+                    remove_moo(ctx, guild_id, channel_id, *message, board).await;
+                }
+            }
+        }
+        Err(ex) => {
+            error!("Failed to get cowboards: {}", ex);
+        }
+    }
+}
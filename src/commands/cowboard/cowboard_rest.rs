@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use serenity::http::Http;
+use serenity::http::request::RequestBuilder as HttpRequest;
+use serenity::http::routing::RouteInfo;
+use serenity::json::Value;
+use serenity::model::channel::Message;
+use crate::Error;
+
+// Serenity 0.11 can't point webhooks at threads, so those go to the API directly.
+const API: &str = "https://discord.com/api/v10";
+
+// How many times to try again when Discord says we're being rate limited.
+const MAX_RETRIES: u32 = 3;
+
+#[derive(Deserialize)]
+pub struct ForumTag {
+    pub id: String,
+    pub name: String
+}
+
+#[derive(Deserialize)]
+struct ForumChannel {
+    #[serde(default)]
+    available_tags: Vec<ForumTag>
+}
+
+// One client for every call, so connections get reused.
+fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(Client::new)
+}
+
+// These requests skip serenity's rate limiter, so wait out any 429s ourselves.
+async fn send(request: impl Fn() -> RequestBuilder) -> Result<Response, Error> {
+    let mut retries = 0;
+    loop {
+        let response = request().send().await?;
+        if response.status() != StatusCode::TOO_MANY_REQUESTS || retries >= MAX_RETRIES {
+            return Ok(response.error_for_status()?);
+        }
+
+        let wait = response.headers()
+            .get("Retry-After")
+            .and_then(|o| o.to_str().ok())
+            .and_then(|o| o.parse::<f64>().ok())
+            .unwrap_or(1.0);
+        tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+        retries += 1;
+    }
+}
+
+fn thread_query(thread_id: Option<u64>) -> String {
+    thread_id.map(|o| format!("&thread_id={o}")).unwrap_or_default()
+}
+
+// Sending without a thread to a forum's webhook needs a `thread_name` in the body, which starts a new post.
+pub async fn execute_webhook(webhook_id: u64, token: &str, thread_id: Option<u64>, body: &Value) -> Result<Message, Error> {
+    let url = format!("{API}/webhooks/{webhook_id}/{token}?wait=true{}", thread_query(thread_id));
+    let message = send(|| client().post(&url).json(body))
+        .await?
+        .json::<Message>()
+        .await?;
+
+    Ok(message)
+}
+
+// Serenity 0.11 doesn't know about forum posts, but starting one is the same `POST /channels/{id}/threads` as a private thread,
+// just with a `message` in the body; this keeps it behind serenity's rate limiter. Returns the post's first message.
+pub async fn create_forum_post(http: &Http, channel_id: u64, body: &Value) -> Result<Message, Error> {
+    let body = body.as_object().ok_or("A forum post needs an object for its body")?;
+    let post = http.create_private_thread(channel_id, body).await?;
+
+    // A forum post's first message shares its ID with the post itself.
+    Ok(http.get_message(post.id.0, post.id.0).await?)
+}
+
+// Whether Discord said the webhook is deleted or its token is no longer valid, as opposed to something that may pass.
+pub fn webhook_gone(ex: &Error) -> bool {
+    let status = if let Some(ex) = ex.downcast_ref::<reqwest::Error>() {
+        ex.status().map(|o| o.as_u16())
+    } else if let Some(serenity::Error::Http(ex)) = ex.downcast_ref::<serenity::Error>() {
+        ex.status_code().map(|o| o.as_u16())
+    } else {
+        None
+    };
+
+    matches!(status, Some(401 | 404))
+}
+
+pub async fn edit_webhook_message(webhook_id: u64, token: &str, thread_id: u64, message_id: u64, body: &Value) -> Result<(), Error> {
+    let url = format!("{API}/webhooks/{webhook_id}/{token}/messages/{message_id}?thread_id={thread_id}");
+    send(|| client().patch(&url).json(body)).await?;
+
+    Ok(())
+}
+
+// Serenity doesn't know about forum tags, but fetching the channel itself can still go through its rate limiter.
+pub async fn get_forum_tags(http: &Http, channel_id: u64) -> Result<Vec<ForumTag>, Error> {
+    let channel = http.request(HttpRequest::new(RouteInfo::GetChannel { channel_id }).build())
+        .await?
+        .json::<ForumChannel>()
+        .await?;
+
+    Ok(channel.available_tags)
+}
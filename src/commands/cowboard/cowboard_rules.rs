@@ -0,0 +1,246 @@
+use tracing::error;
+use serenity::model::id::{GuildId, RoleId};
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::commands::cowboard::cowboard_db_models::CowboardRules;
+
+// Same shape as the other setters: fetch, tweak one thing, save. The reply may mention a role, so it never pings.
+async fn update_rules(ctx: &CowContext<'_>, guild_id: GuildId, success: &str, modify: impl FnOnce(&mut CowboardRules)) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    match db.get_cowboard_rules(guild_id).await {
+        Ok(mut rules) => {
+            modify(&mut rules);
+
+            if let Err(ex) = db.update_cowboard_rules(&rules).await {
+                ctx.say("We couldn't update the cowboard rules, sorry... Try again later?").await?;
+                error!("Failed to update cowboard rules: {}", ex);
+            } else {
+                ctx.send(|m| m.content(success).allowed_mentions(|o| o.empty_users().empty_parse().empty_roles())).await?;
+            }
+        }
+        Err(ex) => {
+            ctx.say("We couldn't get the cowboard rules... try again later?").await?;
+            error!("Failed to get cowboard rules: {}", ex);
+        }
+    }
+
+    Ok(())
+}
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("show", "selfreact", "bots", "accountage", "reactrole", "maxage", "keepdeleted"),
+    guild_only,
+    discard_spare_arguments,
+    description_localized("en-US", "Manage the server-wide rules for what counts towards the cowboards."),
+)]
+pub async fn rules(ctx: CowContext<'_>) -> Result<(), Error> {
+    rules_code(ctx).await
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "Get the server-wide rules for what counts towards the cowboards."),
+    guild_only,
+    discard_spare_arguments
+)]
+pub async fn show(ctx: CowContext<'_>) -> Result<(), Error> {
+    rules_code(ctx).await
+}
+
+pub async fn rules_code(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Ok(rules) = db.get_cowboard_rules(guild_id).await {
+            ctx.send(|m| {
+                m.embeds.clear();
+                m.embed(|e|
+                    e
+                        .title("Cowboard Rules")
+                        .description("These apply to every board in the server.")
+                        .field("Self Reactions", if rules.count_self { "Counted" } else { "Ignored" }, true)
+                        .field("Bot & Webhook Messages", if rules.allow_bots { "Allowed" } else { "Ignored" }, true)
+                        .field("Minimum Account Age", if rules.min_account_age > 0 { format!("{} days", rules.min_account_age) } else { "None".to_string() }, true)
+                        .field("Required Role", rules.required_role.map(|o| format!("<@&{o}>")).unwrap_or_else(|| "None".to_string()), true)
+                        .field("Maximum Message Age", if rules.max_message_age > 0 { format!("{} hours", rules.max_message_age) } else { "None".to_string() }, true)
+                        .field("Deleted Messages", if rules.keep_deleted { "Posts Kept" } else { "Posts Removed" }, true)
+                )
+            }).await?;
+        } else {
+            ctx.say("Failed to fetch the cowboard rules for this server...").await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Toggle whether authors reacting to their own messages counts."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn selfreact(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let count_self = !db.get_cowboard_rules(guild_id).await?.count_self;
+        let success = if count_self {
+            "Authors reacting to their own messages will now count towards the cowboards."
+        } else {
+            "Authors reacting to their own messages will no longer count."
+        };
+
+        update_rules(&ctx, guild_id, success, |r| r.count_self = count_self).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Toggle whether messages from bots and webhooks can reach the cowboards."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn bots(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let allow_bots = !db.get_cowboard_rules(guild_id).await?.allow_bots;
+        let success = if allow_bots {
+            "Messages from bots and webhooks can now reach the cowboards."
+        } else {
+            "Messages from bots and webhooks will no longer reach the cowboards."
+        };
+
+        update_rules(&ctx, guild_id, success, |r| r.allow_bots = allow_bots).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set how old an account must be for its reactions to count."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn accountage(
+    ctx: CowContext<'_>,
+    #[description = "The minimum account age in days; 0 counts everyone."] #[min = 0] #[max = 3650] days: i32)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        if days < 0 {
+            ctx.say("The given number must be positive or zero.").await?;
+            return Ok(())
+        }
+
+        let success = if days == 0 {
+            "Reactions from accounts of any age will now count.".to_string()
+        } else {
+            format!("Only reactions from accounts at least {days} days old will count now.")
+        };
+
+        update_rules(&ctx, guild_id, &success, |r| r.min_account_age = days).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set a role that members need for their reactions to count."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn reactrole(
+    ctx: CowContext<'_>,
+    #[description = "The role to require; leave empty to let anyone count."] role: Option<RoleId>)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let success = match role {
+            Some(role) => format!("Only reactions from members with <@&{role}> will count now."),
+            None => "Reactions from anyone will now count.".to_string()
+        };
+
+        update_rules(&ctx, guild_id, &success, |r| r.required_role = role.map(|o| o.0)).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set how old a message can be before it can't be newly posted to a cowboard."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn maxage(
+    ctx: CowContext<'_>,
+    #[description = "The maximum message age in hours; 0 allows any age."] #[min = 0] #[max = 87600] hours: i32)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        if hours < 0 {
+            ctx.say("The given number must be positive or zero.").await?;
+            return Ok(())
+        }
+
+        let success = if hours == 0 {
+            "Messages of any age can now be posted to the cowboards.".to_string()
+        } else {
+            format!("Only messages from the last {hours} hours can be newly posted to the cowboards now.")
+        };
+
+        update_rules(&ctx, guild_id, &success, |r| r.max_message_age = hours).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Toggle whether cowboard posts stay up after the original message is deleted."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn keepdeleted(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let keep_deleted = !db.get_cowboard_rules(guild_id).await?.keep_deleted;
+        let success = if keep_deleted {
+            "Cowboard posts will now stay up when the original message is deleted."
+        } else {
+            "Cowboard posts will now be removed when the original message is deleted."
+        };
+
+        update_rules(&ctx, guild_id, success, |r| r.keep_deleted = keep_deleted).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use tracing::error;
+use crate::{CowContext, Database, db, cowdb, Error};
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "See the most mooed users, channels, and messages."),
+    guild_only
+)]
+pub async fn stats(
+    ctx: CowContext<'_>,
+    #[description = "The name of a board; leave empty for every board."] board: Option<String>,
+    #[description = "The period to look over: day, week, month, year, or all"] period: Option<String>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let (days, period) = match period.unwrap_or_else(|| "all".to_string()).to_lowercase().as_str() {
+            "day" | "daily" => (1, "past day"),
+            "week" | "weekly" => (7, "past week"),
+            "month" | "monthly" => (30, "past month"),
+            "year" | "yearly" => (365, "past year"),
+            "all" => (0, "all time"),
+            _ => {
+                ctx.say("The period must be one of day, week, month, year, or all.").await?;
+                return Ok(());
+            }
+        };
+
+        if let Some(board) = &board {
+            if db.get_cowboard_config(guild_id, board).await?.is_none() {
+                ctx.say(format!("There's no board named `{board}`; try `.cowboard create` to make one.")).await?;
+                return Ok(());
+            }
+        }
+
+        let stats = match db.get_cowboard_stats(guild_id, board.as_deref(), days).await {
+            Ok(stats) => stats,
+            Err(ex) => {
+                ctx.say("Failed to get cowboard stats.").await?;
+                error!("Failed to get cowboard stats: {}", ex);
+                return Ok(());
+            }
+        };
+
+        if stats.posts == 0 {
+            ctx.say("Nothing has made it onto the cowboard in that time... yet!").await?;
+            return Ok(());
+        }
+
+        let ranked = |lines: Vec<String>| if lines.is_empty() {
+            "Nobody yet!".to_string()
+        } else {
+            lines.iter().enumerate().map(|(i, o)| format!("{}. {}", i + 1, o)).collect::<Vec<_>>().join("\n")
+        };
+
+        let users = ranked(stats.top_users.iter().map(|(id, posts)| format!("<@{id}> - {posts} posts")).collect());
+        let channels = ranked(stats.top_channels.iter().map(|(id, posts)| format!("<#{id}> - {posts} posts")).collect());
+        let messages = ranked(stats.top_messages.iter()
+            .map(|o| format!("[{} reactions](https://discord.com/channels/{}/{}/{}) by <@{}> in `{}`", o.peak_reactions, o.guild_id, o.message_channel_id, o.message_id, o.author_id, o.board))
+            .collect());
+
+        let title = match &board {
+            Some(board) => format!("Cowboard Stats: {board} ({period})"),
+            None => format!("Cowboard Stats ({period})")
+        };
+
+        ctx.send(|m| {
+            m.embeds.clear();
+            m.embed(|e| e
+                .title(title)
+                .description(format!("{} posts with {} reactions at their peak.", stats.posts, stats.reactions))
+                .field("Most Mooed Users", users, false)
+                .field("Most Mooed Channels", channels, false)
+                .field("Top Messages", messages, false)
+            )
+            .allowed_mentions(|o| o.empty_users().empty_parse().empty_roles())
+        }).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
@@ -1,13 +1,22 @@
+mod cowboard_backfill;
 mod cowboard_config;
 mod cowboard_db;
 mod cowboard_db_models;
+mod cowboard_rest;
+pub mod cowboard_digest;
+mod cowboard_rules;
+mod cowboard_stats;
 pub mod cowboard_handler;
 
+use cowboard_backfill::backfill;
 use cowboard_config::*;
+use cowboard_digest::digest;
+use cowboard_rules::rules;
+use cowboard_stats::stats;
 use crate::{CowContext, Error};
 
 #[poise::command(prefix_command, slash_command,
-    subcommands("info", "emote", "addthreshold", "removethreshold", "channel", "webhook"),
+    subcommands("info", "create", "delete", "emote", "addthreshold", "removethreshold", "channel", "webhook", "xpbonus", "tags", "filter", "rules", "stats", "backfill", "digest"),
     discard_spare_arguments,
     description_localized("en-US", "Commands for modifying how the cowboard (starboard) functions."),
     guild_only,
@@ -1,5 +1,6 @@
 pub mod info;
 pub mod rank;
+pub mod rank_history;
 pub mod ban;
 pub mod help;
 pub mod danbooru;
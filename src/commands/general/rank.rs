@@ -8,11 +8,19 @@ use serenity::{
     },
     utils::MessageBuilder
 };
+use std::time::Duration;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::application::component::ButtonStyle;
+use serenity::client::Context;
+use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
 use crate::{Database, db, cowdb, Error, CowContext};
+use crate::models::db_models::MemberPagination;
+use super::rank_history::{history, trending};
 use tracing::{error};
 
 // This prevents us from executing commands when the server has it disabled.
-async fn guild_disabled(ctx: &CowContext<'_>, guild: &GuildId) -> bool {
+pub(super) async fn guild_disabled(ctx: &CowContext<'_>, guild: &GuildId) -> bool {
     let db = cowdb!(ctx);
 
     match db.get_disablements(*guild, ctx.channel_id()).await {
@@ -31,6 +39,27 @@ async fn guild_disabled(ctx: &CowContext<'_>, guild: &GuildId) -> bool {
     false
 }
 
+// Members with an excluded role, who the database can't know about.
+async fn hidden_members(ctx: &CowContext<'_>, db: &Database, server_id: GuildId) -> Vec<UserId> {
+    let exclusions = match db.get_exclusions(server_id).await {
+        Ok(exclusions) => exclusions,
+        Err(ex) => {
+            error!("Failed to get exclusions: {}", ex);
+            return Vec::new();
+        }
+    };
+
+    if exclusions.roles.is_empty() {
+        return Vec::new();
+    }
+
+    ctx.serenity_context().cache.guild_field(server_id, |g| g.members.values()
+        .filter(|m| exclusions.excludes(m.user.id, &m.roles))
+        .map(|m| m.user.id)
+        .collect())
+        .unwrap_or_default()
+}
+
 async fn rank_embed(ctx: &CowContext<'_>, server_id: &GuildId, user: &User) {
     let db = cowdb!(ctx);
 
@@ -51,7 +80,8 @@ async fn rank_embed(ctx: &CowContext<'_>, server_id: &GuildId, user: &User) {
     }
 
     let mut rank_str = String::from("(Unranked)");
-    if let Some(rank) = db.rank_within_members(*server_id, user.id).await.unwrap() {
+    let hidden = hidden_members(ctx, &db, *server_id).await;
+    if let Some(rank) = db.rank_within_members(*server_id, user.id, &hidden).await.unwrap() {
         rank_str = format!("#{rank}");
     }
 
@@ -77,30 +107,104 @@ async fn rank_embed(ctx: &CowContext<'_>, server_id: &GuildId, user: &User) {
     }
 }
 
+async fn season_rank_embed(ctx: &CowContext<'_>, server_id: &GuildId, user: &User, season: i32) {
+    let db = cowdb!(ctx);
+
+    let standing = match db.get_season_standing(*server_id, season, user.id).await {
+        Ok(standing) => standing,
+        Err(ex) => {
+            error!("Failed to get season standing: {}", ex);
+            return;
+        }
+    };
+
+    let mut pfp_url = user.default_avatar_url();
+    if let Some(pfp_custom) = user.avatar_url() {
+        pfp_url = pfp_custom;
+    }
+
+    if let Err(ex) = ctx.send(|m| {
+        m.embeds.clear();
+        m.embed(|e| {
+            e
+                .title(
+                    MessageBuilder::new()
+                        .push_safe(user.name.as_str())
+                        .push("#")
+                        .push(user.discriminator)
+                        .push("'s Ranking in Season ")
+                        .push(season)
+                        .build()
+                )
+                .thumbnail(pfp_url);
+
+            if let Some(standing) = standing {
+                e
+                    .field("Level", standing.exp.level, true)
+                    .field("XP", standing.exp.xp, true)
+                    .field("Rank", format!("#{}", standing.rank), true);
+            } else {
+                e.description("Did not place this season.");
+            }
+
+            e
+    })}).await {
+        error!("Failed to send embed: {}", ex);
+    }
+}
+
 #[poise::command(
     prefix_command,
     slash_command,
     guild_only,
     description_localized("en-US", "Get your current rank."),
-    aliases("course", "class", "classes")
+    aliases("course", "class", "classes"),
+    subcommands("rank_view", "history")
 )]
 pub async fn rank(
     ctx: CowContext<'_>,
-    #[description = "A user to check their rank"] user: Option<UserId>)
+    #[description = "A user to check their rank"] user: Option<UserId>,
+    #[description = "A past season to check the final rank of"] #[min = 1] season: Option<i32>)
+-> Result<(), Error> {
+    rank_code(ctx, user, season).await
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "view",
+    description_localized("en-US", "Get your current rank.")
+)]
+pub async fn rank_view(
+    ctx: CowContext<'_>,
+    #[description = "A user to check their rank"] user: Option<UserId>,
+    #[description = "A past season to check the final rank of"] #[min = 1] season: Option<i32>)
 -> Result<(), Error> {
+    rank_code(ctx, user, season).await
+}
+
+pub async fn rank_code(ctx: CowContext<'_>, user: Option<UserId>, season: Option<i32>) -> Result<(), Error> {
     if let Some(server_id) = ctx.guild_id() {
         if guild_disabled(&ctx, &server_id).await {
             return Ok(());
         }
 
-        if let Some(other_id) = user {
+        let target = if let Some(other_id) = user {
             if let Ok(other_user) = other_id.to_user(&ctx).await {
-                rank_embed(&ctx, &server_id, &other_user).await;
+                other_user
             } else {
                 ctx.say("Could not find user...").await?;
+                return Ok(());
             }
         } else {
-            rank_embed(&ctx, &server_id, ctx.author()).await;
+            ctx.author().clone()
+        };
+
+        if let Some(season) = season {
+            season_rank_embed(&ctx, &server_id, &target, season).await;
+        } else {
+            rank_embed(&ctx, &server_id, &target).await;
         }
     } else {
         ctx.say("This command can only be run in a server.").await?;
@@ -109,16 +213,96 @@ pub async fn rank(
     Ok(())
 }
 
+// How long the leaderboard buttons stay active after the last click, in seconds.
+const LEADERBOARD_TIMEOUT: u64 = 120;
+
+async fn leaderboard_page(db: &Database, server_id: GuildId, season: Option<i32>, page: i32, hidden: &[UserId]) -> Result<MemberPagination, Error> {
+    if let Some(season) = season {
+        db.top_season_members(server_id, season, page).await
+    } else {
+        db.top_members(server_id, page, hidden).await
+    }
+}
+
+async fn leaderboard_rank(db: &Database, server_id: GuildId, season: Option<i32>, user_id: UserId, hidden: &[UserId]) -> Result<Option<i64>, Error> {
+    if let Some(season) = season {
+        Ok(db.get_season_standing(server_id, season, user_id).await?.map(|s| s.rank))
+    } else {
+        db.rank_within_members(server_id, user_id, hidden).await
+    }
+}
+
+fn leaderboard_embed<'a>(e: &'a mut CreateEmbed, title: &str, pagination: &MemberPagination) -> &'a mut CreateEmbed {
+    let content = pagination.members.iter()
+        .enumerate()
+        .map(|o| {
+            let (index, member) = o;
+            format!("`#{}` <@{}> - Level {}, {} xp", (index as i32) + 10 * pagination.current_page + 1, member.id, member.exp.level, member.exp.xp)
+        })
+        .reduce(|a, b| {format!("{a}\n{b}")})
+        .unwrap_or_else(|| "There is nothing on this page.".to_string());
+
+    e
+        .title(title)
+        .description(content)
+        .footer(|e| e.text(format!("Page {}/{}", pagination.current_page + 1, pagination.last_page.max(1))))
+}
+
+fn leaderboard_buttons<'a>(c: &'a mut CreateComponents, pagination: &MemberPagination) -> &'a mut CreateComponents {
+    let first = pagination.current_page <= 0;
+    let last = pagination.current_page + 1 >= pagination.last_page;
+
+    c.create_action_row(|r| {
+        r
+            .create_button(|b| b.style(ButtonStyle::Secondary).label("First").custom_id("levels:first").disabled(first))
+            .create_button(|b| b.style(ButtonStyle::Primary).label("Previous").custom_id("levels:previous").disabled(first))
+            .create_button(|b| b.style(ButtonStyle::Primary).label("Next").custom_id("levels:next").disabled(last))
+            .create_button(|b| b.style(ButtonStyle::Secondary).label("Last").custom_id("levels:last").disabled(last))
+            .create_button(|b| b.style(ButtonStyle::Success).label("Jump to Me").custom_id("levels:me"))
+    })
+}
+
+// Answers a button press privately, so the clicker doesn't see "This interaction failed".
+async fn leaderboard_notice(serenity: &Context, interaction: &MessageComponentInteraction, content: &str) {
+    if let Err(ex) = interaction.create_interaction_response(serenity, |r| r
+        .kind(InteractionResponseType::ChannelMessageWithSource)
+        .interaction_response_data(|d| d.ephemeral(true).content(content))
+    ).await {
+        error!("Failed to respond to leaderboard button: {}", ex);
+    }
+}
+
 #[poise::command(
     prefix_command,
     slash_command,
     guild_only,
-    description_localized("en-US", "Get the current rankings in the server.")
+    description_localized("en-US", "Get the current rankings in the server."),
+    subcommands("levels_view", "trending")
 )]
 pub async fn levels(
     ctx: CowContext<'_>,
-    #[description = "The page of rankings to fetch"] #[min = 1] page: Option<i32>)
+    #[description = "The page of rankings to fetch"] #[min = 1] page: Option<i32>,
+    #[description = "A past season to show the final rankings of"] #[min = 1] season: Option<i32>)
+-> Result<(), Error> {
+    levels_code(ctx, page, season).await
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "view",
+    description_localized("en-US", "Get the current rankings in the server.")
+)]
+pub async fn levels_view(
+    ctx: CowContext<'_>,
+    #[description = "The page of rankings to fetch"] #[min = 1] page: Option<i32>,
+    #[description = "A past season to show the final rankings of"] #[min = 1] season: Option<i32>)
 -> Result<(), Error> {
+    levels_code(ctx, page, season).await
+}
+
+pub async fn levels_code(ctx: CowContext<'_>, page: Option<i32>, season: Option<i32>) -> Result<(), Error> {
     let db = cowdb!(ctx);
     if let Some(server_id) = ctx.guild_id() {
         if guild_disabled(&ctx, &server_id).await {
@@ -126,30 +310,88 @@ pub async fn levels(
         }
 
         let level_page = page.unwrap_or(1).max(1);
-        match db.top_members(server_id, level_page - 1).await {
-            Ok(pagination) => {
-                let content = pagination.members.into_iter()
-                    .enumerate()
-                    .map(|o| {
-                        let (index, member) = o;
-                        format!("`#{}` <@{}> - Level {}, {} xp", (index as i32) + 10 * (level_page - 1) + 1, member.id, member.exp.level, member.exp.xp)
-                    })
-                    .reduce(|a, b| {format!("{a}\n{b}")})
-                    .unwrap_or_else(|| "There is nothing on this page.".to_string());
-                ctx.send(|m| {
-                    m.embeds.clear();
-                    m.embed(|e|
-                        e
-                            .title("Top Users")
-                            .description(content)
-                            .footer(|e| e.text(format!("Page {}/{}", level_page, pagination.last_page)))
-                    )}).await?;
-            },
+        let title = season.map(|s| format!("Top Users of Season {s}")).unwrap_or_else(|| "Top Users".to_string());
+
+        let hidden = hidden_members(&ctx, &db, server_id).await;
+
+        let mut pagination = match leaderboard_page(&db, server_id, season, level_page - 1, &hidden).await {
+            Ok(pagination) => pagination,
             Err(ex) => {
                 ctx.say("Failed to get rankings.".to_string()).await?;
                 error!("Failed to get rankings: {}", ex);
+                return Ok(());
+            }
+        };
+
+        let reply = ctx.send(|m| {
+            m.embeds.clear();
+            m.embed(|e| leaderboard_embed(e, &title, &pagination))
+                .components(|c| leaderboard_buttons(c, &pagination))
+        }).await?;
+
+        let message = reply.message().await?;
+        let serenity = ctx.serenity_context();
+
+        // Keep serving button presses until nobody has clicked for a while.
+        while let Some(interaction) = message
+            .await_component_interaction(serenity)
+            .timeout(Duration::from_secs(LEADERBOARD_TIMEOUT))
+            .await {
+            let last_page = (pagination.last_page - 1).max(0);
+            let target_page = match interaction.data.custom_id.as_str() {
+                "levels:first" => 0,
+                "levels:previous" => pagination.current_page - 1,
+                "levels:next" => pagination.current_page + 1,
+                "levels:last" => last_page,
+                "levels:me" => {
+                    match leaderboard_rank(&db, server_id, season, interaction.user.id, &hidden).await {
+                        Ok(Some(rank)) => ((rank - 1) / 10) as i32,
+                        Ok(None) => {
+                            leaderboard_notice(serenity, &interaction, "You are not on this leaderboard.").await;
+                            continue;
+                        }
+                        Err(ex) => {
+                            error!("Failed to get rank within members: {}", ex);
+                            leaderboard_notice(serenity, &interaction, "Failed to find your rank.").await;
+                            continue;
+                        }
+                    }
+                }
+                _ => {
+                    // Not one of ours, but Discord still wants an answer.
+                    if let Err(ex) = interaction.create_interaction_response(serenity, |r| r.kind(InteractionResponseType::DeferredUpdateMessage)).await {
+                        error!("Failed to acknowledge leaderboard button: {}", ex);
+                    }
+                    continue;
+                }
+            }.clamp(0, last_page);
+
+            match leaderboard_page(&db, server_id, season, target_page, &hidden).await {
+                Ok(new_pagination) => pagination = new_pagination,
+                Err(ex) => {
+                    error!("Failed to get rankings: {}", ex);
+                    leaderboard_notice(serenity, &interaction, "Failed to get rankings.").await;
+                    continue;
+                }
+            }
+
+            if let Err(ex) = interaction.create_interaction_response(serenity, |r| r
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d
+                    .embed(|e| leaderboard_embed(e, &title, &pagination))
+                    .components(|c| leaderboard_buttons(c, &pagination))
+                )
+            ).await {
+                error!("Failed to update leaderboard: {}", ex);
             }
         }
+
+        // Time's up; take the buttons away so nobody clicks a dead leaderboard.
+        reply.edit(ctx, |m| {
+            m.embeds.clear();
+            m.embed(|e| leaderboard_embed(e, &title, &pagination))
+                .components(|c| c)
+        }).await?;
     } else {
         ctx.say("This command can only be run in a server.").await?;
     }
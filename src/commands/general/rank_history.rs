@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+use serenity::model::channel::AttachmentType;
+use serenity::model::id::UserId;
+use serenity::prelude::TypeMap;
+use tokio::sync::RwLock;
+use tokio::time;
+use tracing::{error, info};
+use crate::{Database, db, cowdb, Error, CowContext};
+use crate::util::line_chart_png;
+use super::rank::guild_disabled;
+
+const TRENDING_SIZE: usize = 10;
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Get a chart of your experience over time.")
+)]
+pub async fn history(
+    ctx: CowContext<'_>,
+    #[description = "A user to check the history of"] user: Option<UserId>,
+    #[description = "How many days back to show, 30 by default"] #[min = 2] #[max = 365] days: Option<i32>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(server_id) = ctx.guild_id() {
+        if guild_disabled(&ctx, &server_id).await {
+            return Ok(());
+        }
+
+        let target = if let Some(other_id) = user {
+            if let Ok(other_user) = other_id.to_user(&ctx).await {
+                other_user
+            } else {
+                ctx.say("Could not find user...").await?;
+                return Ok(());
+            }
+        } else {
+            ctx.author().clone()
+        };
+
+        let days = days.unwrap_or(30).clamp(2, 365);
+        let history = match db.get_xp_history(server_id, target.id, days).await {
+            Ok(history) => history,
+            Err(ex) => {
+                ctx.say("Failed to get experience history.").await?;
+                error!("Failed to get experience history: {}", ex);
+                return Ok(());
+            }
+        };
+
+        if history.len() < 2 {
+            ctx.say(format!("There isn't enough history for {} yet; check back in a few days!", target.name)).await?;
+            return Ok(());
+        }
+
+        let max_level = history.iter().map(|s| s.exp.level).max().unwrap_or(0);
+        let curve = db.xp_curve(max_level).await?;
+        let points: Vec<_> = history.iter().map(|s| (s.date, s.exp.total(&curve))).collect();
+
+        let chart = line_chart_png(&points)?;
+        let (first, last) = (points[0], points[points.len() - 1]);
+        let gained = last.1 - first.1;
+
+        ctx.send(|m| {
+            m.embeds.clear();
+            m.embed(|e| e
+                .title(format!("{}'s Experience History", target.name))
+                .description(format!("Gained {} xp over the last {} days.", gained, days))
+                .footer(|f| f.text(format!("{} ({} xp) to {} ({} xp)", first.0.format("%b %d"), first.1, last.0.format("%b %d"), last.1)))
+                .attachment("history.png")
+            ).attachment(AttachmentType::Bytes { data: Cow::from(chart), filename: "history.png".to_string() })
+        }).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Get the users who gained the most experience recently.")
+)]
+pub async fn trending(
+    ctx: CowContext<'_>,
+    #[description = "The period to compare over: day, week, or month"] period: Option<String>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(server_id) = ctx.guild_id() {
+        if guild_disabled(&ctx, &server_id).await {
+            return Ok(());
+        }
+
+        let (days, period) = match period.unwrap_or_else(|| "week".to_string()).to_lowercase().as_str() {
+            "day" | "daily" => (1, "day"),
+            "week" | "weekly" => (7, "week"),
+            "month" | "monthly" => (30, "month"),
+            _ => {
+                ctx.say("The period must be one of day, week, or month.").await?;
+                return Ok(());
+            }
+        };
+
+        let gains = match db.get_xp_gains(server_id, days).await {
+            Ok(gains) => gains,
+            Err(ex) => {
+                ctx.say("Failed to get trending users.").await?;
+                error!("Failed to get experience gains: {}", ex);
+                return Ok(());
+            }
+        };
+
+        let max_level = gains.iter().map(|g| g.before.level.max(g.after.level)).max().unwrap_or(0);
+        let curve = db.xp_curve(max_level).await?;
+
+        let mut ranked: Vec<_> = gains.iter()
+            .map(|g| (g.id, g.after.total(&curve) - g.before.total(&curve)))
+            .filter(|(_, gained)| *gained > 0)
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let content = ranked.iter()
+            .take(TRENDING_SIZE)
+            .enumerate()
+            .map(|(index, (id, gained))| format!("`#{}` <@{}> - {} xp", index + 1, id, gained))
+            .reduce(|a, b| format!("{a}\n{b}"))
+            .unwrap_or_else(|| "Nobody has gained any experience yet.".to_string());
+
+        ctx.send(|m| {
+            m.embeds.clear();
+            m.embed(|e| e
+                .title(format!("Trending Users (past {period})"))
+                .description(content)
+            )
+        }).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+// Refreshes today's experience snapshot for every member every hour.
+pub async fn record_history(data: Arc<RwLock<TypeMap>>) {
+    let mut interval = time::interval(Duration::from_secs(60 * 60));
+    loop {
+        interval.tick().await;
+        let db = {
+            let ctx_global = data.read().await;
+            ctx_global.get::<Database>().expect("Couldn't find database").clone()
+        };
+
+        match db.snapshot_xp().await {
+            Ok(count) => info!("Recorded {} experience snapshots", count),
+            Err(ex) => error!("Failed to record experience snapshots: {}", ex)
+        }
+    }
+}
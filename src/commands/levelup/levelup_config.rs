@@ -0,0 +1,203 @@
+use tracing::error;
+use serenity::model::id::ChannelId;
+use serenity::utils::MessageBuilder;
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::models::db_models::{AnnounceMode, LevelUpConfig};
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "Get the current settings for level-up announcements."),
+    guild_only,
+    discard_spare_arguments
+)]
+pub async fn info(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+pub async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Ok(config) = db.get_level_up_config(guild_id).await {
+            let milestones = if config.milestone_interval > 1 {
+                format!("Every {} levels", config.milestone_interval)
+            } else {
+                "Every level".to_string()
+            };
+
+            ctx.send(|m| {
+                m.embeds.clear();
+                m.embed(|e|
+                    e
+                        .title("Level-up Settings")
+                        .field("Mode", config.mode, true)
+                        .field("Channel", config.channel.map(|o| format!("<#{o}>")).unwrap_or_else(|| "No Dedicated Channel".to_string()), true)
+                        .field("Milestones", milestones, true)
+                        .field("Rank Changes Only", config.rank_changes_only, true)
+                        .field("Template", MessageBuilder::new().push_mono_safe(config.template.as_deref().unwrap_or(LevelUpConfig::DEFAULT_TEMPLATE)).build(), false)
+                )
+            }).await?;
+        } else {
+            ctx.say("Failed to fetch level-up settings for this server...").await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set where level ups are announced: channel, dedicated, dm, or none."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn mode(
+    ctx: CowContext<'_>,
+    #[description = "One of \"channel\", \"dedicated\", \"dm\", or \"none\"."] mode: String,
+    #[description = "The channel to announce in, for the dedicated mode."] channel: Option<ChannelId>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    let announce_mode = match AnnounceMode::try_from(mode.as_str()) {
+        Ok(announce_mode) => announce_mode,
+        Err(_) => {
+            ctx.say("The mode must be one of `channel`, `dedicated`, `dm`, or `none`.").await?;
+            return Ok(());
+        }
+    };
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Some(channel) = channel {
+            if !ctx.guild().map(|g| g.channels.contains_key(&channel)).unwrap_or(false) {
+                ctx.say("Could not find channel in this server!").await?;
+                return Ok(())
+            }
+        }
+
+        match db.get_level_up_config(guild_id).await {
+            Ok(mut config) => {
+                if announce_mode == AnnounceMode::Dedicated {
+                    if let Some(channel) = channel {
+                        config.channel = Some(channel.0);
+                    } else if config.channel.is_none() {
+                        ctx.say("Please provide a channel to announce level ups in.").await?;
+                        return Ok(())
+                    }
+                }
+
+                config.mode = announce_mode;
+
+                if let Err(ex) = db.update_level_up_config(&config).await {
+                    ctx.say("We couldn't update the level-up settings, sorry... Try again later?").await?;
+                    error!("Failed to update level-up settings: {}", ex);
+                } else {
+                    ctx.say(format!("Level ups will now be announced with mode: {announce_mode}.")).await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the level-up settings... try again later?").await?;
+                error!("Failed to get level-up settings: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set the level-up message. Placeholders: {user}, {old_level}, {new_level}, {new_role}."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn template(
+    ctx: CowContext<'_>,
+    #[description = "The message template; leave empty to restore the default."] #[rest] template: Option<String>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(template) = &template {
+        if template.len() > 1024 {
+            ctx.say("The template must be at most 1024 characters long.").await?;
+            return Ok(())
+        }
+    }
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_level_up_config(guild_id).await {
+            Ok(mut config) => {
+                config.template = template;
+
+                if let Err(ex) = db.update_level_up_config(&config).await {
+                    ctx.say("We couldn't update the level-up settings, sorry... Try again later?").await?;
+                    error!("Failed to update level-up settings: {}", ex);
+                } else if config.template.is_none() {
+                    ctx.say("Restored the default level-up message!").await?;
+                } else {
+                    ctx.say("Successfully updated the level-up message!").await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the level-up settings... try again later?").await?;
+                error!("Failed to get level-up settings: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Only announce every n-th level, or only when a member's rank changes."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn milestone(
+    ctx: CowContext<'_>,
+    #[description = "Announce every n-th level; 1 announces every level."] #[min = 1] interval: i32,
+    #[description = "Only announce when a member gets a new rank."] rank_changes_only: Option<bool>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if interval <= 0 {
+        ctx.say("The given number must be positive.").await?;
+        return Ok(())
+    }
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_level_up_config(guild_id).await {
+            Ok(mut config) => {
+                config.milestone_interval = interval;
+                config.rank_changes_only = rank_changes_only.unwrap_or(false);
+
+                if let Err(ex) = db.update_level_up_config(&config).await {
+                    ctx.say("We couldn't update the level-up settings, sorry... Try again later?").await?;
+                    error!("Failed to update level-up settings: {}", ex);
+                } else if config.rank_changes_only {
+                    ctx.say("Level ups will now only be announced on rank changes.").await?;
+                } else {
+                    ctx.say(format!("Level ups will now be announced every {interval} levels, and on rank changes.")).await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the level-up settings... try again later?").await?;
+                error!("Failed to get level-up settings: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+mod levelup_config;
+
+use levelup_config::*;
+use crate::{CowContext, Error};
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("info", "mode", "template", "milestone"),
+    discard_spare_arguments,
+    description_localized("en-US", "Commands for configuring how level ups are announced."),
+    guild_only,
+    identifying_name = "Level-up Announcements"
+)]
+pub async fn levelup(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
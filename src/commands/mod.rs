@@ -1,11 +1,15 @@
-mod general;
-mod rank_config;
+pub mod general;
+pub mod rank_config;
 mod timeout;
 pub mod ucm;
 pub mod cowboard;
-mod music;
+pub mod music;
 pub mod minecraft;
 mod gpt;
+mod xp;
+mod levelup;
+pub mod season;
+mod spam_filter;
 
 use std::{collections::HashSet};
 
@@ -21,6 +25,10 @@ use crate::Error;
 use crate::commands::general::*;
 use crate::commands::rank_config::rankconfig;
 use crate::commands::timeout::timeout;
+use crate::commands::xp::xp;
+use crate::commands::levelup::levelup;
+use crate::commands::season::season;
+use crate::commands::spam_filter::spamfilter;
 use crate::commands::ucm::ucm;
 use crate::commands::cowboard::cowboard;
 use crate::commands::music::music;
@@ -43,6 +51,10 @@ pub async fn get_framework(pref: &str, _app_id: UserId, owners: HashSet<UserId>)
             banoverwatchplayers(),
             rankconfig(),
             timeout(),
+            xp(),
+            levelup(),
+            season(),
+            spamfilter(),
             ucm(),
             cowboard(),
             music(),
@@ -1,11 +1,13 @@
 mod music_commands;
+pub mod music_filters;
+pub mod music_handler;
 mod spotify;
 
 use crate::{CowContext, Error};
 use music_commands::*;
 
 #[poise::command(prefix_command, slash_command,
-    subcommands("help", "join", "leave", "play", "playlist", "pause", "now_playing", "skip", "queue"),
+    subcommands("help", "join", "leave", "play", "playlist", "pause", "now_playing", "skip", "queue", "remove", "move_track", "shuffle", "clear", "skipto", "dedupe", "stop", "loop_mode", "volume", "seek", "filter", "defaultvolume"),
     discard_spare_arguments,
     description_localized("en-US", "Commands for playing music."),
     guild_only,
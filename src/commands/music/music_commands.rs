@@ -1,9 +1,15 @@
-use lavalink_rs::model::{TrackQueue};
+use std::collections::HashSet;
+use std::time::Duration;
+use lavalink_rs::model::{Node, TrackQueue};
+use rand::seq::SliceRandom;
 use tracing::error;
 use regex::Regex;
+use serenity::model::id::UserId;
 use serenity::utils::MessageBuilder;
-use crate::{Error, Lavalink};
+use crate::{Database, db, cowdb, Error, Lavalink};
 use crate::commands::music::spotify;
+use crate::commands::music::music_handler::{LoopMode, MusicLoops, current_mode, set_mode};
+use crate::commands::music::music_filters::{FilterPreset, MusicFilters, build_filters, preset_names, toggle_preset};
 use crate::CowContext;
 
 #[poise::command(
@@ -16,7 +22,7 @@ pub async fn help(ctx: CowContext<'_>) -> Result<(), Error> {
 }
 
 pub async fn help_code(ctx: CowContext<'_>) -> Result<(), Error> {
-    ctx.say("`help, join, leave, play, playlist, pause, now_playing, skip, queue`").await?;
+    ctx.say("`help, join, leave, play, playlist, pause, now_playing, skip, queue, remove, move, shuffle, clear, skipto, dedupe, stop, loop, volume, seek, filter, defaultvolume`").await?;
 
     Ok(())
 }
@@ -51,6 +57,18 @@ pub async fn join_interactive(ctx: &CowContext<'_>) -> Result<(), Error> {
             };
 
             lava_client.create_session_with_songbird(&connection_info).await?;
+
+            // Lavalink keeps the volume for the whole session, so this only needs to happen when joining.
+            let db = cowdb!(ctx);
+            match db.get_music_config(guild_id).await {
+                Ok(config) if config.default_volume != 100 => {
+                    if let Err(ex) = lava_client.volume(guild_id.0, config.default_volume as u16).await {
+                        error!("Failed to set default volume: {}", ex);
+                    }
+                }
+                Ok(_) => {}
+                Err(ex) => error!("Failed to get music config: {}", ex)
+            }
             ctx.say(format!("Joined <#{connect_to}>")).await?;
         }
         Err(ex) => {
@@ -98,6 +116,8 @@ pub async fn leave(ctx: CowContext<'_>) -> Result<(), Error> {
             let data = serenity.data.read().await;
             let lava_client = data.get::<Lavalink>().unwrap().clone();
             lava_client.destroy(guild_id.0).await?;
+            data.get::<MusicLoops>().unwrap().lock().unwrap().remove(&guild_id.0);
+            data.get::<MusicFilters>().unwrap().lock().unwrap().remove(&guild_id.0);
         }
 
         ctx.say("Disconnected from VC. Goodbye!").await?;
@@ -152,7 +172,7 @@ pub async fn play(
                 return Ok(());
             }
 
-            if let Err(why) = &lava_client.play(guild_id.0, query_information.tracks[0].clone()).queue()
+            if let Err(why) = &lava_client.play(guild_id.0, query_information.tracks[0].clone()).requester(ctx.author().id).queue()
                 .await
             {
                 error!("Failed to queue: {}", why);
@@ -208,7 +228,7 @@ pub async fn playlist(
                 match lava_client.get_tracks(&query).await {
                     Ok(tracks) => {
                         for track in &tracks.tracks {
-                            if let Err(why) = &lava_client.play(guild_id, track.clone()).queue()
+                            if let Err(why) = &lava_client.play(guild_id, track.clone()).requester(ctx.author().id).queue()
                                 .await
                             {
                                 error!("Failed to queue from playlist: {}", why);
@@ -280,9 +300,9 @@ pub async fn pause(ctx: CowContext<'_>) -> Result<(), Error> {
     discard_spare_arguments
 )]
 pub async fn now_playing(ctx: CowContext<'_>) -> Result<(), Error> {
-    let lava_client = {
+    let (lava_client, loops, filters) = {
         let data = ctx.serenity_context().data.read().await;
-        data.get::<Lavalink>().unwrap().clone()
+        (data.get::<Lavalink>().unwrap().clone(), data.get::<MusicLoops>().unwrap().clone(), data.get::<MusicFilters>().unwrap().clone())
     };
 
     if let Some(node) = lava_client.nodes().await.get(&ctx.guild_id().unwrap().0) {
@@ -292,6 +312,8 @@ pub async fn now_playing(ctx: CowContext<'_>) -> Result<(), Error> {
             let youtube_id = re.captures(&info.uri).and_then(|caps| caps.get(1).map(|m| m.as_str()));
             let spotify_thumbail = spotify::get_thumbnail(&info.uri).await;
             let server_name = ctx.guild().map(|o| o.name);
+            let mode = current_mode(&loops, ctx.guild_id().unwrap().0);
+            let active = filters.lock().unwrap().get(&ctx.guild_id().unwrap().0).cloned().unwrap_or_default();
 
             ctx.send(|m| {
                 m.embeds.clear();
@@ -311,6 +333,14 @@ pub async fn now_playing(ctx: CowContext<'_>) -> Result<(), Error> {
                         e.field("Requested By", format!("<@{requester}>"), true);
                     }
 
+                    if mode != LoopMode::Off {
+                        e.field("Loop", mode.name(), true);
+                    }
+
+                    if !active.is_empty() {
+                        e.field("Filters", preset_names(&active), true);
+                    }
+
                     if let Some(id) = youtube_id {
                         e.thumbnail(format!("https://img.youtube.com/vi/{id}/maxresdefault.jpg"));
                     } else if let Some(url) = spotify_thumbail {
@@ -418,9 +448,9 @@ pub async fn queue(
     ctx: CowContext<'_>,
     #[description = "The page of the queue to display"] #[min = 1] page: Option<usize>)
 -> Result<(), Error> {
-    let lava_client = {
+    let (lava_client, loops) = {
         let data = ctx.serenity_context().data.read().await;
-        data.get::<Lavalink>().unwrap().clone()
+        (data.get::<Lavalink>().unwrap().clone(), data.get::<MusicLoops>().unwrap().clone())
     };
 
     let mut page_num = if let Some(arg_page) = page {
@@ -442,6 +472,7 @@ pub async fn queue(
 
         let page = &pages[page_num - 1];
         let server_name = guild_id.name(ctx.serenity_context());
+        let mode = current_mode(&loops, guild_id.0);
 
         ctx.send(|m| {
             m.embeds.clear();
@@ -457,7 +488,8 @@ pub async fn queue(
                         a
                     })
                     .title("Now Playing")
-                    .field("Queued", page, false);
+                    .field("Queued", page, false)
+                    .footer(|f| f.text(format!("Loop: {}", mode.name())));
 
                 if let Some(now_playing) = &node.now_playing {
                     e.description(generate_line(now_playing));
@@ -474,4 +506,589 @@ pub async fn queue(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// The playing track stays at the front of the queue until it finishes, so it can't be moved around.
+fn first_queued(node: &Node) -> usize {
+    if node.now_playing.is_some() { 1 } else { 0 }
+}
+
+fn requested_by(song: &TrackQueue, user: UserId) -> bool {
+    song.requester.map(|o| o.0 == user.0).unwrap_or(false)
+}
+
+fn song_title(song: &TrackQueue) -> &str {
+    song.track.info.as_ref().map(|o| o.title.as_str()).unwrap_or("Unknown")
+}
+
+// Anyone who can manage messages can rearrange the whole queue; everyone else only their own tracks.
+async fn can_manage_queue(ctx: &CowContext<'_>) -> bool {
+    match ctx.author_member().await {
+        Some(member) => member.permissions(ctx.serenity_context()).map(|o| o.manage_messages()).unwrap_or(false),
+        None => false
+    }
+}
+
+// Positions are the ones shown by `queue`.
+fn check_position(node: &Node, position: usize) -> Result<(), String> {
+    if position == 0 || position > node.queue.len() {
+        Err(format!("There are only {} tracks in the queue.", node.queue.len()))
+    } else if position <= first_queued(node) {
+        Err("That's the track playing right now; use `skip` instead.".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Remove a track from the queue."),
+    discard_spare_arguments
+)]
+pub async fn remove(
+    ctx: CowContext<'_>,
+    #[description = "The position of the track in the queue."] #[min = 1] position: usize)
+-> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let manager = can_manage_queue(&ctx).await;
+    let author = ctx.author().id;
+
+    let reply = if let Some(mut node) = lava_client.nodes().await.get_mut(&ctx.guild_id().unwrap().0) {
+        match check_position(&node, position) {
+            Err(message) => message,
+            Ok(_) if !manager && !requested_by(&node.queue[position - 1], author) => "You can only remove tracks you requested.".to_string(),
+            Ok(_) => {
+                let song = node.queue.remove(position - 1);
+                MessageBuilder::new().push("Removed: ").push_mono_line_safe(song_title(&song)).build()
+            }
+        }
+    } else {
+        "Nothing is playing at the moment.".to_string()
+    };
+
+    ctx.say(reply).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "move",
+    description_localized("en-US", "Move a track to another position in the queue."),
+    discard_spare_arguments
+)]
+pub async fn move_track(
+    ctx: CowContext<'_>,
+    #[description = "The current position of the track."] #[min = 1] from: usize,
+    #[description = "The position to move the track to."] #[min = 1] to: usize)
+-> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let manager = can_manage_queue(&ctx).await;
+    let author = ctx.author().id;
+
+    let reply = if let Some(mut node) = lava_client.nodes().await.get_mut(&ctx.guild_id().unwrap().0) {
+        match check_position(&node, from).and_then(|_| check_position(&node, to)) {
+            Err(message) => message,
+            Ok(_) if !manager && !requested_by(&node.queue[from - 1], author) => "You can only move tracks you requested.".to_string(),
+            Ok(_) => {
+                let song = node.queue.remove(from - 1);
+                let message = MessageBuilder::new().push("Moved ").push_mono_safe(song_title(&song)).push(format!(" to position {to}.")).build();
+                node.queue.insert(to - 1, song);
+                message
+            }
+        }
+    } else {
+        "Nothing is playing at the moment.".to_string()
+    };
+
+    ctx.say(reply).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Shuffle the upcoming tracks in the queue."),
+    discard_spare_arguments
+)]
+pub async fn shuffle(ctx: CowContext<'_>) -> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let manager = can_manage_queue(&ctx).await;
+    let author = ctx.author().id;
+
+    let reply = if let Some(mut node) = lava_client.nodes().await.get_mut(&ctx.guild_id().unwrap().0) {
+        let first = first_queued(&node);
+
+        if node.queue.len() <= first + 1 {
+            "There's nothing to shuffle.".to_string()
+        } else if !manager && !node.queue[first..].iter().all(|o| requested_by(o, author)) {
+            "You can only shuffle the queue when every track in it is yours.".to_string()
+        } else {
+            node.queue[first..].shuffle(&mut rand::thread_rng());
+            "Shuffled the queue.".to_string()
+        }
+    } else {
+        "Nothing is playing at the moment.".to_string()
+    };
+
+    ctx.say(reply).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Clear the upcoming tracks from the queue."),
+    discard_spare_arguments
+)]
+pub async fn clear(ctx: CowContext<'_>) -> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let manager = can_manage_queue(&ctx).await;
+    let author = ctx.author().id;
+
+    let reply = if let Some(mut node) = lava_client.nodes().await.get_mut(&ctx.guild_id().unwrap().0) {
+        let first = first_queued(&node);
+        let upcoming = node.queue.split_off(first);
+        let count = upcoming.len();
+
+        if manager {
+            format!("Cleared {count} tracks from the queue.")
+        } else {
+            // Without permission to manage the queue, only clear the tracks this user asked for.
+            node.queue.extend(upcoming.into_iter().filter(|o| !requested_by(o, author)));
+            format!("Cleared {} of your tracks from the queue.", count - (node.queue.len() - first))
+        }
+    } else {
+        "Nothing is playing at the moment.".to_string()
+    };
+
+    ctx.say(reply).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Skip straight to a track in the queue."),
+    discard_spare_arguments
+)]
+pub async fn skipto(
+    ctx: CowContext<'_>,
+    #[description = "The position of the track to skip to."] #[min = 1] position: usize)
+-> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let guild_id = ctx.guild_id().unwrap();
+    let manager = can_manage_queue(&ctx).await;
+    let author = ctx.author().id;
+
+    let result = if let Some(mut node) = lava_client.nodes().await.get_mut(&guild_id.0) {
+        let first = first_queued(&node);
+
+        match check_position(&node, position) {
+            Err(message) => Err(message),
+            Ok(_) if !manager && !node.queue[first..position - 1].iter().all(|o| requested_by(o, author)) => Err("You can only skip over tracks you requested.".to_string()),
+            Ok(_) => {
+                let skipped = node.queue.drain(first..position - 1).count();
+                let target = node.queue[first].clone();
+
+                // With nothing playing there's no track to hand over to this one, so it gets started below instead.
+                if first == 0 {
+                    node.now_playing = Some(target.clone());
+                }
+
+                Ok((skipped, first == 1, target))
+            }
+        }
+    } else {
+        Err("Nothing is playing at the moment.".to_string())
+    };
+
+    match result {
+        Ok((skipped, playing, target)) => {
+            // Skipping the playing track is what starts the one we skipped to.
+            if playing {
+                lava_client.skip(guild_id).await;
+            } else if let Err(ex) = lava_client.play(guild_id.0, target.track.clone()).start().await {
+                error!("Failed to start the track skipped to: {}", ex);
+                ctx.say("Failed to start playing, sorry... Try again later?").await?;
+                return Ok(());
+            }
+
+            ctx.say(MessageBuilder::new().push(format!("Skipped {} tracks, now playing: ", skipped + playing as usize)).push_mono_line_safe(song_title(&target)).build()).await?;
+        }
+        Err(message) => {
+            ctx.say(message).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Remove repeated tracks from the queue, keeping whoever asked first."),
+    discard_spare_arguments
+)]
+pub async fn dedupe(ctx: CowContext<'_>) -> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let manager = can_manage_queue(&ctx).await;
+    let author = ctx.author().id;
+
+    let reply = if let Some(mut node) = lava_client.nodes().await.get_mut(&ctx.guild_id().unwrap().0) {
+        let before = node.queue.len();
+        let mut seen = HashSet::new();
+
+        // Every track counts towards what's been seen, but without permission to manage the queue only this user's repeats go.
+        node.queue.retain(|o| {
+            let first = seen.insert(o.track.info.as_ref().map(|i| i.uri.clone()).unwrap_or_else(|| o.track.track.clone()));
+            first || !(manager || requested_by(o, author))
+        });
+
+        match before - node.queue.len() {
+            0 => "There are no repeated tracks in the queue.".to_string(),
+            removed => format!("Removed {removed} repeated tracks from the queue.")
+        }
+    } else {
+        "Nothing is playing at the moment.".to_string()
+    };
+
+    ctx.say(reply).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Stop the music and clear the queue."),
+    discard_spare_arguments
+)]
+pub async fn stop(ctx: CowContext<'_>) -> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let guild_id = ctx.guild_id().unwrap();
+    let manager = can_manage_queue(&ctx).await;
+    let author = ctx.author().id;
+
+    let result = if let Some(mut node) = lava_client.nodes().await.get_mut(&guild_id.0) {
+        if !manager && !node.queue.iter().all(|o| requested_by(o, author)) {
+            Err("You can only stop the music when every track in the queue is yours.")
+        } else {
+            node.queue.clear();
+            Ok(())
+        }
+    } else {
+        Err("Nothing is playing at the moment.")
+    };
+
+    match result {
+        Ok(_) => {
+            if let Err(ex) = lava_client.stop(guild_id).await {
+                error!("Failed to stop music: {}", ex);
+                ctx.say("Failed to stop the player...").await?;
+            } else {
+                ctx.say("Stopped the player and cleared the queue.").await?;
+            }
+        }
+        Err(message) => {
+            ctx.say(message).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "loop",
+    description_localized("en-US", "Repeat the current track or the whole queue."),
+    discard_spare_arguments
+)]
+pub async fn loop_mode(
+    ctx: CowContext<'_>,
+    #[description = "Either off, track, or queue."] mode: Option<String>)
+-> Result<(), Error> {
+    let loops = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<MusicLoops>().unwrap().clone()
+    };
+
+    let guild_id = ctx.guild_id().unwrap();
+
+    let mode = match mode.map(|o| o.to_lowercase()) {
+        None => {
+            ctx.say(format!("Looping is set to `{}`.", current_mode(&loops, guild_id.0).name().to_lowercase())).await?;
+            return Ok(());
+        }
+        Some(mode) => match mode.as_str() {
+            "off" | "none" => LoopMode::Off,
+            "track" | "song" | "one" => LoopMode::Track,
+            "queue" | "all" => LoopMode::Queue,
+            _ => {
+                ctx.say("The loop mode must be one of off, track, or queue.").await?;
+                return Ok(());
+            }
+        }
+    };
+
+    set_mode(&loops, guild_id.0, mode);
+
+    ctx.say(match mode {
+        LoopMode::Off => "Stopped looping.",
+        LoopMode::Track => "Looping the current track.",
+        LoopMode::Queue => "Looping the whole queue."
+    }).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Change the volume of the music player."),
+    discard_spare_arguments
+)]
+pub async fn volume(
+    ctx: CowContext<'_>,
+    #[description = "The volume, from 0 to 200."] #[min = 0] #[max = 200] volume: Option<u16>)
+-> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let guild_id = ctx.guild_id().unwrap();
+
+    let volume = match volume {
+        Some(volume) => volume,
+        None => {
+            let current = lava_client.nodes().await.get(&guild_id.0).map(|o| o.volume);
+            match current {
+                Some(current) => ctx.say(format!("The volume is at {current}%.")).await?,
+                None => ctx.say("Nothing is playing at the moment.").await?
+            };
+            return Ok(());
+        }
+    };
+
+    if volume > 200 {
+        ctx.say("The volume must be between 0 and 200.").await?;
+        return Ok(());
+    }
+
+    if let Err(ex) = lava_client.volume(guild_id.0, volume).await {
+        ctx.say("Failed to change the volume...").await?;
+        error!("Failed to set volume: {}", ex);
+    } else {
+        ctx.say(format!("Set the volume to {volume}%.")).await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Jump to a point in the current track."),
+    discard_spare_arguments
+)]
+pub async fn seek(
+    ctx: CowContext<'_>,
+    #[description = "How far into the track to jump, like 1:30 or 1m30s."] timestamp: String)
+-> Result<(), Error> {
+    let lava_client = {
+        let data = ctx.serenity_context().data.read().await;
+        data.get::<Lavalink>().unwrap().clone()
+    };
+
+    let guild_id = ctx.guild_id().unwrap();
+
+    let position = match crate::util::timestamp_to_ms(&timestamp) {
+        Some(position) => position,
+        None => {
+            ctx.say("The timestamp should look like 1:30 or 1m30s.").await?;
+            return Ok(());
+        }
+    };
+
+    let length = lava_client.nodes().await.get(&guild_id.0)
+        .and_then(|o| o.now_playing.as_ref().and_then(|o| o.track.info.as_ref().map(|o| o.length)));
+
+    match length {
+        None => {
+            ctx.say("Nothing is playing at the moment.").await?;
+        }
+        Some(length) if position >= length => {
+            ctx.say(format!("That's past the end of the track, which is only {} long.", crate::util::from_ms(length))).await?;
+        }
+        Some(length) => {
+            if let Err(ex) = lava_client.seek(guild_id.0, Duration::from_millis(position)).await {
+                ctx.say("Failed to seek in the track...").await?;
+                error!("Failed to seek: {}", ex);
+            } else {
+                ctx.say(format!("Jumped to {}/{}.", crate::util::from_ms(position), crate::util::from_ms(length))).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Toggle an audio filter: bassboost, nightcore, vaporwave, 8d, karaoke, or off."),
+    discard_spare_arguments
+)]
+pub async fn filter(
+    ctx: CowContext<'_>,
+    #[description = "One of bassboost, nightcore, vaporwave, 8d, karaoke, or off."] preset: Option<String>)
+-> Result<(), Error> {
+    let (lava_client, filters) = {
+        let data = ctx.serenity_context().data.read().await;
+        (data.get::<Lavalink>().unwrap().clone(), data.get::<MusicFilters>().unwrap().clone())
+    };
+
+    let guild_id = ctx.guild_id().unwrap();
+
+    let preset = match preset {
+        Some(preset) => preset,
+        None => {
+            let active = filters.lock().unwrap().get(&guild_id.0).cloned().unwrap_or_default();
+            ctx.say(format!("Active filters: {}", preset_names(&active))).await?;
+            return Ok(());
+        }
+    };
+
+    let clear = preset.eq_ignore_ascii_case("off") || preset.eq_ignore_ascii_case("none");
+    let parsed = FilterPreset::parse(&preset);
+    if !clear && parsed.is_none() {
+        ctx.say("The filter must be one of bassboost, nightcore, vaporwave, 8d, karaoke, or off.").await?;
+        return Ok(());
+    }
+
+    if lava_client.nodes().await.get(&guild_id.0).is_none() {
+        ctx.say("Nothing is playing at the moment.").await?;
+        return Ok(());
+    }
+
+    let (active, enabled) = {
+        let filters = filters.lock().unwrap();
+        let mut active = filters.get(&guild_id.0).cloned().unwrap_or_default();
+        let enabled = match parsed {
+            Some(preset) => toggle_preset(&mut active, preset),
+            None => {
+                active.clear();
+                false
+            }
+        };
+
+        (active, enabled)
+    };
+
+    if let Err(ex) = lava_client.set_filters(guild_id.0, build_filters(&active)).await {
+        ctx.say("Failed to change the filters...").await?;
+        error!("Failed to set filters: {}", ex);
+        return Ok(());
+    }
+
+    filters.lock().unwrap().insert(guild_id.0, active);
+
+    ctx.say(match parsed {
+        Some(preset) if enabled => format!("Turned on the {} filter.", preset.name()),
+        Some(preset) => format!("Turned off the {} filter.", preset.name()),
+        None => "Turned off all filters.".to_string()
+    }).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set the volume the music player starts at in this server."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn defaultvolume(
+    ctx: CowContext<'_>,
+    #[description = "The volume, from 0 to 200."] #[min = 0] #[max = 200] volume: Option<i32>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+    let guild_id = ctx.guild_id().unwrap();
+
+    match db.get_music_config(guild_id).await {
+        Ok(mut config) => {
+            match volume {
+                None => {
+                    ctx.say(format!("The music player starts at {}% volume.", config.default_volume)).await?;
+                }
+                Some(volume) if !(0..=200).contains(&volume) => {
+                    ctx.say("The volume must be between 0 and 200.").await?;
+                }
+                Some(volume) => {
+                    config.default_volume = volume;
+
+                    if let Err(ex) = db.update_music_config(&config).await {
+                        ctx.say("We couldn't update the default volume, sorry... Try again later?").await?;
+                        error!("Failed to update music config: {}", ex);
+                    } else {
+                        ctx.say(format!("The music player will now start at {volume}% volume.")).await?;
+                    }
+                }
+            }
+        }
+        Err(ex) => {
+            ctx.say("We couldn't get the music settings... try again later?").await?;
+            error!("Failed to get music config: {}", ex);
+        }
+    }
+
+    Ok(())
+}
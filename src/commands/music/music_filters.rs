@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use lavalink_rs::model::{Band, Filters, Karaoke, Rotation, Timescale};
+use serenity::prelude::TypeMapKey;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterPreset {
+    BassBoost,
+    Nightcore,
+    Vaporwave,
+    EightD,
+    Karaoke
+}
+
+impl FilterPreset {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bassboost" | "bass" => Some(FilterPreset::BassBoost),
+            "nightcore" => Some(FilterPreset::Nightcore),
+            "vaporwave" => Some(FilterPreset::Vaporwave),
+            "8d" => Some(FilterPreset::EightD),
+            "karaoke" => Some(FilterPreset::Karaoke),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterPreset::BassBoost => "Bass Boost",
+            FilterPreset::Nightcore => "Nightcore",
+            FilterPreset::Vaporwave => "Vaporwave",
+            FilterPreset::EightD => "8D",
+            FilterPreset::Karaoke => "Karaoke"
+        }
+    }
+
+    // Both of these change the playback speed, so only one can be on at a time.
+    fn conflicts(&self, other: FilterPreset) -> bool {
+        matches!((self, other), (FilterPreset::Nightcore, FilterPreset::Vaporwave) | (FilterPreset::Vaporwave, FilterPreset::Nightcore))
+    }
+}
+
+// Lavalink only keeps one set of filters per player, so we remember which presets make it up.
+pub struct MusicFilters;
+
+impl TypeMapKey for MusicFilters {
+    type Value = Arc<Mutex<HashMap<u64, Vec<FilterPreset>>>>;
+}
+
+// Turns the preset on, or off if it already was; returns whether it is now on.
+pub fn toggle_preset(active: &mut Vec<FilterPreset>, preset: FilterPreset) -> bool {
+    if active.contains(&preset) {
+        active.retain(|o| *o != preset);
+        false
+    } else {
+        active.retain(|o| !o.conflicts(preset));
+        active.push(preset);
+        true
+    }
+}
+
+pub fn preset_names(active: &[FilterPreset]) -> String {
+    if active.is_empty() {
+        "None".to_string()
+    } else {
+        active.iter().map(|o| o.name()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+pub fn build_filters(active: &[FilterPreset]) -> Filters {
+    let mut filters = Filters::default();
+
+    for preset in active {
+        match preset {
+            FilterPreset::BassBoost => {
+                let gains = [0.25, 0.2, 0.15, 0.1, 0.05, -0.05];
+                filters.equalizer = Some(gains.iter().enumerate().map(|(band, gain)| Band { band: band as u8, gain: *gain }).collect());
+            }
+            FilterPreset::Nightcore => {
+                filters.timescale = Some(Timescale { speed: 1.2, pitch: 1.2, rate: 1.0 });
+            }
+            FilterPreset::Vaporwave => {
+                filters.timescale = Some(Timescale { speed: 0.85, pitch: 0.8, rate: 1.0 });
+            }
+            FilterPreset::EightD => {
+                filters.rotation = Some(Rotation { rotation_hz: 0.2 });
+            }
+            FilterPreset::Karaoke => {
+                filters.karaoke = Some(Karaoke { level: 1.0, mono_level: 1.0, filter_band: 220.0, filter_width: 100.0 });
+            }
+        }
+    }
+
+    filters
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use lavalink_rs::LavalinkClient;
+use lavalink_rs::model::{TrackFinish, TrackQueue, TrackStart};
+use serenity::prelude::TypeMapKey;
+use tracing::error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopMode {
+    #[default]
+    Off,
+    Track,
+    Queue
+}
+
+impl LoopMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoopMode::Off => "Off",
+            LoopMode::Track => "Track",
+            LoopMode::Queue => "Queue"
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PlayerLoop {
+    pub mode: LoopMode,
+    // Lavalink drops a track from the node once it finishes, so keep a copy to requeue it.
+    current: Option<TrackQueue>
+}
+
+pub struct MusicLoops;
+
+impl TypeMapKey for MusicLoops {
+    type Value = Arc<Mutex<HashMap<u64, PlayerLoop>>>;
+}
+
+pub fn current_mode(loops: &<MusicLoops as TypeMapKey>::Value, guild_id: u64) -> LoopMode {
+    loops.lock().unwrap().get(&guild_id).map(|o| o.mode).unwrap_or_default()
+}
+
+pub fn set_mode(loops: &<MusicLoops as TypeMapKey>::Value, guild_id: u64, mode: LoopMode) {
+    loops.lock().unwrap().entry(guild_id).or_default().mode = mode;
+}
+
+pub async fn track_start(loops: &<MusicLoops as TypeMapKey>::Value, client: LavalinkClient, event: TrackStart) {
+    let playing = client.nodes().await.get(&event.guild_id.0).and_then(|o| o.now_playing.clone());
+    loops.lock().unwrap().entry(event.guild_id.0).or_default().current = playing;
+}
+
+pub async fn track_finish(loops: &<MusicLoops as TypeMapKey>::Value, client: LavalinkClient, event: TrackFinish) {
+    let guild_id = event.guild_id.0;
+
+    let (mode, song) = {
+        let mut loops = loops.lock().unwrap();
+        match loops.get_mut(&guild_id) {
+            Some(player) => (player.mode, player.current.take()),
+            None => return
+        }
+    };
+
+    // Skipping or stopping ends a track with a different reason; only repeat ones that played out.
+    if mode == LoopMode::Off || event.reason != "FINISHED" {
+        return;
+    }
+
+    let song = match song {
+        Some(song) => song,
+        None => return
+    };
+
+    if mode == LoopMode::Track {
+        // None when there's nothing left queued; otherwise whether the repeat has to be played over the top.
+        let restart = match client.nodes().await.get_mut(&guild_id) {
+            Some(mut node) if !node.queue.is_empty() => {
+                // Holding the node stops the queue from moving on while we work out where it is.
+                match node.now_playing.as_ref().map(|o| o.track.track == event.track) {
+                    // Not cleared yet, so it's still at the front; the repeat goes right behind it.
+                    Some(true) => {
+                        node.queue.insert(1, song.clone());
+                        Some(false)
+                    }
+                    // The next track already took over; put the repeat back in front and play it over the top.
+                    Some(false) => {
+                        node.queue.insert(0, song.clone());
+                        node.now_playing = Some(song.clone());
+                        Some(true)
+                    }
+                    None => {
+                        node.queue.insert(0, song.clone());
+                        Some(false)
+                    }
+                }
+            }
+            _ => None
+        };
+
+        match restart {
+            Some(true) => {
+                if let Err(ex) = client.play(guild_id, song.track).start().await {
+                    error!("Failed to restart looped track: {}", ex);
+                }
+            }
+            Some(false) => {}
+            None => requeue(&client, guild_id, song).await
+        }
+
+        return;
+    }
+
+    requeue(&client, guild_id, song).await;
+}
+
+// Queueing normally also restarts the player if that was the last track.
+async fn requeue(client: &LavalinkClient, guild_id: u64, song: TrackQueue) {
+    let mut play = client.play(guild_id, song.track);
+    if let Some(requester) = song.requester {
+        play = play.requester(requester);
+    }
+
+    if let Err(ex) = play.queue().await {
+        error!("Failed to requeue looped track: {}", ex);
+    }
+}
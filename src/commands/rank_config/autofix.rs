@@ -0,0 +1,262 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tracing::{error, info};
+use serenity::{
+    CacheAndHttp,
+    model::{channel::AttachmentType, id::{ChannelId, GuildId}},
+    prelude::TypeMap
+};
+use tokio::sync::RwLock;
+use tokio::time;
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::models::db_models::AutofixConfig;
+use super::diagnostics::{reconcile, FixOptions};
+
+// The hour (UTC) after which the nightly run happens; this is late night in California.
+const AUTOFIX_HOUR: i32 = 10;
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("info", "toggle", "options", "channel", "dryrun"),
+    guild_only,
+    discard_spare_arguments,
+    description_localized("en-US", "Configure the nightly rank role auto-fix."),
+)]
+pub async fn autofix(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+async fn update_config(ctx: &CowContext<'_>, guild_id: GuildId, success: &str, modify: impl FnOnce(&mut AutofixConfig)) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    match db.get_autofix_config(guild_id).await {
+        Ok(mut config) => {
+            modify(&mut config);
+
+            if let Err(ex) = db.update_autofix_config(&config).await {
+                ctx.say("We couldn't update the auto-fix settings, sorry... Try again later?").await?;
+                error!("Failed to update auto-fix settings: {}", ex);
+            } else {
+                ctx.say(success).await?;
+            }
+        }
+        Err(ex) => {
+            ctx.say("We couldn't get the auto-fix settings... try again later?").await?;
+            error!("Failed to get auto-fix settings: {}", ex);
+        }
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Get the current settings for the nightly auto-fix."),
+    discard_spare_arguments
+)]
+pub async fn info(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+pub async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Ok(config) = db.get_autofix_config(guild_id).await {
+            let yes_no = |b: bool| if b { "Yes" } else { "No" };
+
+            ctx.send(|m| {
+                m.embeds.clear();
+                m.embed(|e|
+                    e
+                        .title("Nightly Auto-fix Settings")
+                        .field("Status", if config.enabled { "Enabled" } else { "Disabled" }, true)
+                        .field("Mode", if config.dry_run { "Dry run (report only)" } else { "Apply fixes" }, true)
+                        .field("Log Channel", config.log_channel.map(|o| format!("<#{o}>")).unwrap_or_else(|| "No log channel".to_string()), true)
+                        .field("Fix Multiple Ranks", yes_no(config.fix_multiple), true)
+                        .field("Remove Ranks", yes_no(config.fix_remove), true)
+                        .field("Demote", yes_no(config.fix_demote), true)
+                        .field("Last Run", config.last_run.map(|o| format!("<t:{}:R>", o.and_utc().timestamp())).unwrap_or_else(|| "Never".to_string()), true)
+                )
+            }).await?;
+        } else {
+            ctx.say("Failed to fetch auto-fix settings for this server...").await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Enable/disable the nightly auto-fix."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn toggle(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let config = db.get_autofix_config(guild_id).await?;
+        if !config.enabled && config.log_channel.is_none() {
+            ctx.say("Set a log channel first with `.rankconfig autofix channel`, so there's somewhere to report to.").await?;
+            return Ok(());
+        }
+
+        let enabled = !config.enabled;
+        let success = if enabled { "Enabled the nightly auto-fix." } else { "Disabled the nightly auto-fix." };
+        update_config(&ctx, guild_id, success, |c| c.enabled = enabled).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Choose which non-trivial problems the nightly auto-fix handles."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn options(
+    ctx: CowContext<'_>,
+    #[description = "Fix users with multiple valid ranks"] option_multiple: bool,
+    #[description = "Remove ranks from people who shouldn't have a rank"] option_remove: bool,
+    #[description = "Demote users who have a higher rank than they should"] option_demote: bool
+) -> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        update_config(&ctx, guild_id, "Successfully updated the auto-fix options!", |c| {
+            c.fix_multiple = option_multiple;
+            c.fix_remove = option_remove;
+            c.fix_demote = option_demote;
+        }).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Sets the channel to post auto-fix summaries and reports in."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn channel(
+    ctx: CowContext<'_>,
+    #[description = "A channel to post in; defaults to this one."] channel: Option<ChannelId>)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let log_channel = channel.unwrap_or_else(|| ctx.channel_id());
+
+        if !ctx.guild().map(|g| g.channels.contains_key(&log_channel)).unwrap_or(false) {
+            ctx.say("Could not find channel in this server!").await?;
+            return Ok(())
+        }
+
+        update_config(&ctx, guild_id, &format!("Auto-fix reports will be posted in <#{log_channel}>."), |c| c.log_channel = Some(log_channel.0)).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set whether the nightly auto-fix only reports what it would change."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn dryrun(
+    ctx: CowContext<'_>,
+    #[description = "Whether to only report changes instead of applying them."] enabled: bool)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let success = if enabled { "The nightly auto-fix will only report what it would change." } else { "The nightly auto-fix will now apply its changes." };
+        update_config(&ctx, guild_id, success, |c| c.dry_run = enabled).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+async fn run_autofix(db: &Database, ctx: &Arc<CacheAndHttp>, config: &AutofixConfig) -> Result<(), Error> {
+    let guild_id = GuildId::from(config.id);
+    let options = FixOptions {
+        multiple: config.fix_multiple,
+        remove: config.fix_remove,
+        demote: config.fix_demote
+    };
+
+    let report = reconcile(db, ctx, guild_id, &options, config.dry_run).await?;
+
+    if let Some(log_channel) = config.log_channel.map(ChannelId::from) {
+        let title = if config.dry_run { "Nightly Role Auto-fix (Dry Run)" } else { "Nightly Role Auto-fix" };
+        let details = if config.dry_run && !report.fixes.is_empty() {
+            let ranks = db.get_roles(guild_id).await?;
+            Some(report.details(&ranks))
+        } else {
+            None
+        };
+
+        log_channel.send_message(&ctx.http, |m| {
+            m.embed(|e| e
+                .title(title)
+                .description(report.summary())
+            );
+
+            if let Some(details) = &details {
+                m.add_file(AttachmentType::Bytes { data: Cow::from(details.as_bytes()), filename: "autofix-report.txt".to_string() });
+            }
+
+            m
+        }).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn check_autofixes(data: Arc<RwLock<TypeMap>>, ctx: Arc<CacheAndHttp>) {
+    let mut interval_min = time::interval(Duration::from_secs(10 * 60));
+    loop {
+        interval_min.tick().await;
+        let db = {
+            let ctx_global = data.read().await;
+            ctx_global.get::<Database>().expect("Couldn't find database").clone()
+        };
+
+        match db.get_due_autofixes(AUTOFIX_HOUR).await {
+            Ok(configs) => {
+                for mut config in configs {
+                    match run_autofix(&db, &ctx, &config).await {
+                        Ok(_) => info!("Ran nightly auto-fix for server {}", config.id),
+                        Err(ex) => error!("Failed to run nightly auto-fix for server {}: {}", config.id, ex)
+                    }
+
+                    // Mark it as run even if it failed, so we don't retry every tick.
+                    config.last_run = Some(Utc::now().naive_utc());
+                    if let Err(ex) = db.update_autofix_config(&config).await {
+                        error!("Failed to record the auto-fix run: {}", ex);
+                    }
+                }
+            },
+            Err(ex) => {
+                error!("Failed to query due auto-fixes: {}", ex);
+            }
+        }
+    }
+}
@@ -1,15 +1,20 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use tracing::error;
 use crate::{CowContext, cowdb, Error};
 use serenity::{
+    http::CacheHttp,
     model::{
         id::{
-            RoleId
+            GuildId,
+            RoleId,
+            UserId
         }
     },
     utils::MessageBuilder
 };
 use crate::{Database, db};
+use crate::models::db_models::Rank;
 
 #[poise::command(
     prefix_command,
@@ -33,14 +38,34 @@ pub async fn scan(ctx: CowContext<'_>) -> Result<(), Error> {
             )
         }).await?;
 
+        let stack = db.get_role_stacking(guild_id).await?;
         let roles = db.get_roles(guild_id).await?;
-        let role_set = roles.into_iter().filter_map(|r| r.role_id).collect::<HashSet<_>>();
+        let role_set = roles.iter().filter_map(|r| r.role_id).collect::<HashSet<_>>();
         let users = db.get_users(guild_id).await?;
         for u in users {
             if let Ok(member) = guild_id.member(&ctx, u.user).await {
                 let member_role_set: HashSet<RoleId> = HashSet::from_iter(member.roles.iter().cloned());
                 let intersection = role_set.intersection(&member_role_set).collect::<HashSet<_>>();
-                if let Some(expected_role) = u.role_id {
+                if stack {
+                    // Stacking: every rank at or below their level, nothing above.
+                    let expected = stacked_roles(&roles, u.exp.level);
+                    let missing = expected.difference(&member_role_set).collect::<Vec<_>>();
+                    let excess = intersection.into_iter().filter(|r| !expected.contains(*r)).collect::<Vec<_>>();
+                    if missing.is_empty() && excess.is_empty() {
+                        continue; // Correct: exactly the earned roles
+                    }
+
+                    message.push("<@").push(u.user).push(">");
+                    if !missing.is_empty() {
+                        message.push(" is missing: ");
+                        missing.into_iter().for_each(|r| { message.push(" ").role(r).push(" "); });
+                    }
+                    if !excess.is_empty() {
+                        message.push(" has excess roles: ");
+                        excess.into_iter().for_each(|r| { message.push(" ").role(r).push(" "); });
+                    }
+                    message.push("\n");
+                } else if let Some(expected_role) = u.role_id {
                     if intersection.contains(&expected_role) && intersection.len() == 1 {
                         continue; // Correct: one role and it's the expected one
                     }
@@ -101,127 +126,248 @@ pub async fn fix(
 ) -> Result<(), Error> {
     let db = cowdb!(ctx);
     if let Some(guild_id) = ctx.guild_id() {
-        /*
-            There are several invalid cases we have to worry about:
-            - The user shouldn't have the role, and yet they do have conflicting roles (non-trivial) -> remove
-            - The user should have the role, and:
-              - they *do not* have any conflicting roles (trivial)
-              - they have one conflicting role
-                - and they should be higher up (trivial)
-                - and they should be lower down (non-trivial) -> demote
-              - they have multiple conflicting roles (non-trivial) -> multiple
+        let discord_message = ctx.send(|m| {
+            m.embeds.clear();
+            m.embed(|e| e
+                .title("Role Auto-fix")
+                .description("Now fixing roles, please wait warmly...")
+            )
+        }).await?;
 
-             The trivial cases will be done by default, and the non-trivial cases can be done by options.
-         */
+        let options = FixOptions {
+            multiple: option_multiple.unwrap_or(false),
+            remove: option_remove.unwrap_or(false),
+            demote: option_demote.unwrap_or(false)
+        };
 
-        let (mut count_trivial, mut count_multiple, mut count_remove, mut count_demote, mut count_error, mut total_error, mut total) = (0, 0, 0, 0, 0, 0, 0);
+        let report = reconcile(&db, ctx.serenity_context(), guild_id, &options, false).await?;
 
-        let discord_message = ctx.send(|m| {
+        discord_message.edit(ctx, |m| {
             m.embeds.clear();
             m.embed(|e| e
                 .title("Role Auto-fix")
-                .description("Now fixing roles, please wait warmly...")
+                .description(report.summary())
             )
         }).await?;
-        
-        let roles = db.get_roles(guild_id).await?;
-        let role_map = roles.into_iter().filter(|r| r.role_id.is_some()).map(|r| (r.role_id.unwrap(), r.min_level)).collect::<HashMap<_, _>>();
-        let role_set: HashSet<RoleId> = role_map.keys().cloned().collect(); // Mildly disgusting.
-        let users = db.get_users(guild_id).await?;
-        for u in users {
-            if let Ok(mut member) = guild_id.member(&ctx, u.user).await {
-                total += 1;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
 
-                let member_role_set: HashSet<RoleId> = HashSet::from_iter(member.roles.iter().cloned());
-                let intersection = role_set.intersection(&member_role_set).collect::<HashSet<_>>();
-                if let Some(expected_role) = u.role_id {
-                    if intersection.contains(&expected_role) && intersection.len() == 1 {
-                        continue; // Correct: one role and it's the expected one
-                    }
-                    total_error += 1;
-
-                    if intersection.is_empty() { // They do not have the role, and need it
-                        if let Err(ex) = member.add_role(&ctx, expected_role).await {
-                            error!("Failed to add role: {}", ex);
-                            count_error += 1;
-                        } else {
-                            count_trivial += 1;
-                        }
-                    } else if intersection.len() == 1 { // They have another role in place
-                        let existing_role = intersection.into_iter().next().unwrap();
-                        let promote = role_map[existing_role] < role_map[&expected_role];
-                        if promote || option_demote.unwrap_or(false) { // Promote them
-                            if let Err(ex) = member.remove_role(&ctx, existing_role).await {
-                                error!("Failed to remove role for demoting: {}", ex);
-                                count_error += 1;
-                            }
-
-                            if let Err(ex) = member.add_role(&ctx, expected_role).await {
-                                error!("Failed to add role for promoting/demoting: {}", ex);
-                                count_error += 1;
-                            } else if promote {
-                                count_trivial += 1;
-                            } else {
-                                count_demote += 1;
-                            }
-                        }
-                    } else if option_multiple.unwrap_or(false) { // We have multiple to deal with
-                        for r in intersection {
-                            if *r == expected_role {
-                                continue;
-                            }
-
-                            if let Err(ex) = member.remove_role(&ctx, r).await {
-                                error!("Failed to remove excess roles: {}", ex);
-                                count_error += 1;
-                            }
-                        }
+    Ok(())
+}
 
-                        if !member.roles.contains(&expected_role) {
-                            if let Err(ex) = member.add_role(&ctx, expected_role).await {
-                                error!("Failed to add role: {}", ex);
-                                count_error += 1;
-                            }
-                        }
+pub struct FixOptions {
+    pub multiple: bool,
+    pub remove: bool,
+    pub demote: bool
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FixKind {
+    Trivial,
+    Multiple,
+    Remove,
+    Demote
+}
+
+impl Display for FixKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixKind::Trivial => write!(f, "missing rank"),
+            FixKind::Multiple => write!(f, "multiple ranks"),
+            FixKind::Remove => write!(f, "revoke ranks"),
+            FixKind::Demote => write!(f, "demote")
+        }
+    }
+}
+
+// A change to one member's roles; applied all at once, or just reported on a dry run.
+pub struct RoleFix {
+    pub user: UserId,
+    pub name: String,
+    pub kind: FixKind,
+    pub add: Vec<RoleId>,
+    pub remove: Vec<RoleId>
+}
+
+#[derive(Default)]
+pub struct FixReport {
+    pub total: i32,
+    pub total_error: i32,
+    pub count_trivial: i32,
+    pub count_multiple: i32,
+    pub count_remove: i32,
+    pub count_demote: i32,
+    pub count_error: i32,
+    pub fixes: Vec<RoleFix>
+}
+
+impl FixReport {
+    pub fn summary(&self) -> String {
+        format!("Processed {} members in the database with {} errors found:\n\
+            - Trivial fixes: {}\n\
+            - Fixes for multiple roles: {}\n\
+            - Members with their roles fully revoked: {}\n\
+            - Members demoted: {}\n\
+            - Errors adding/removing roles: {}",
+            self.total, self.total_error, self.count_trivial, self.count_multiple, self.count_remove, self.count_demote, self.count_error)
+    }
+
+    // A plain-text listing of every change, for dry runs.
+    pub fn details(&self, ranks: &[Rank]) -> String {
+        let role_name = |id: &RoleId| ranks.iter()
+            .find(|r| r.role_id == Some(*id))
+            .map(|r| r.name.clone())
+            .unwrap_or_else(|| id.to_string());
 
-                        count_multiple += 1;
+        self.fixes.iter()
+            .map(|fix| {
+                let mut line = format!("{} ({}) [{}]:", fix.name, fix.user, fix.kind);
+                fix.add.iter().for_each(|r| line.push_str(&format!(" +{}", role_name(r))));
+                fix.remove.iter().for_each(|r| line.push_str(&format!(" -{}", role_name(r))));
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn count(&mut self, kind: FixKind) {
+        match kind {
+            FixKind::Trivial => self.count_trivial += 1,
+            FixKind::Multiple => self.count_multiple += 1,
+            FixKind::Remove => self.count_remove += 1,
+            FixKind::Demote => self.count_demote += 1
+        }
+    }
+}
+
+/*
+    There are several invalid cases we have to worry about:
+    - The user shouldn't have the role, and yet they do have conflicting roles (non-trivial) -> remove
+    - The user should have the role, and:
+      - they *do not* have any conflicting roles (trivial)
+      - they have one conflicting role
+        - and they should be higher up (trivial)
+        - and they should be lower down (non-trivial) -> demote
+      - they have multiple conflicting roles (non-trivial) -> multiple
+
+     The trivial cases will be done by default, and the non-trivial cases can be done by options.
+     On a dry run, the counts are what would have been fixed, and nothing is touched.
+ */
+pub async fn reconcile(db: &Database, cache_http: impl CacheHttp, guild_id: GuildId, options: &FixOptions, dry_run: bool) -> Result<FixReport, Error> {
+    let mut report = FixReport::default();
+
+    let stack = db.get_role_stacking(guild_id).await?;
+    let roles = db.get_roles(guild_id).await?;
+    let role_map = roles.iter().filter(|r| r.role_id.is_some()).map(|r| (r.role_id.unwrap(), r.min_level)).collect::<HashMap<_, _>>();
+    let role_set: HashSet<RoleId> = role_map.keys().cloned().collect(); // Mildly disgusting.
+    let users = db.get_users(guild_id).await?;
+    for u in users {
+        if let Ok(mut member) = guild_id.member(&cache_http, u.user).await {
+            report.total += 1;
+
+            let member_role_set: HashSet<RoleId> = HashSet::from_iter(member.roles.iter().cloned());
+            let intersection = role_set.intersection(&member_role_set).cloned().collect::<HashSet<_>>();
+            let mut fixes: Vec<(FixKind, Vec<RoleId>, Vec<RoleId>)> = Vec::new();
+
+            if stack {
+                /*
+                    Stacking is simpler: missing ranks are always trivial to add, and extra ranks are
+                    either a demotion (they still have some ranks) or a removal (they have none).
+                 */
+                let expected = stacked_roles(&roles, u.exp.level);
+                let missing = expected.difference(&member_role_set).cloned().collect::<Vec<_>>();
+                let excess = intersection.iter().filter(|r| !expected.contains(*r)).cloned().collect::<Vec<_>>();
+                if missing.is_empty() && excess.is_empty() {
+                    continue; // Correct: exactly the earned roles
+                }
+                report.total_error += 1;
+
+                if !missing.is_empty() {
+                    fixes.push((FixKind::Trivial, missing, Vec::new()));
+                }
+
+                let (kind, revoke) = if expected.is_empty() { (FixKind::Remove, options.remove) } else { (FixKind::Demote, options.demote) };
+                if !excess.is_empty() && revoke {
+                    fixes.push((kind, Vec::new(), excess));
+                }
+            } else if let Some(expected_role) = u.role_id {
+                if intersection.contains(&expected_role) && intersection.len() == 1 {
+                    continue; // Correct: one role and it's the expected one
+                }
+                report.total_error += 1;
+
+                if intersection.is_empty() { // They do not have the role, and need it
+                    fixes.push((FixKind::Trivial, vec![expected_role], Vec::new()));
+                } else if intersection.len() == 1 { // They have another role in place
+                    let existing_role = *intersection.iter().next().unwrap();
+                    let promote = role_map[&existing_role] < role_map[&expected_role];
+                    if promote || options.demote {
+                        let kind = if promote { FixKind::Trivial } else { FixKind::Demote };
+                        fixes.push((kind, vec![expected_role], vec![existing_role]));
                     }
+                } else if options.multiple { // We have multiple to deal with
+                    let excess = intersection.iter().filter(|r| **r != expected_role).cloned().collect::<Vec<_>>();
+                    let missing = if member.roles.contains(&expected_role) { Vec::new() } else { vec![expected_role] };
+                    fixes.push((FixKind::Multiple, missing, excess));
+                }
+            } else {
+                if intersection.is_empty() {
+                    continue; // Correct: no roles
+                }
+
+                report.total_error += 1;
+
+                if options.remove {
+                    fixes.push((FixKind::Remove, Vec::new(), intersection.into_iter().collect()));
+                }
+            }
+
+            for (kind, add, remove) in fixes {
+                if dry_run {
+                    report.count(kind);
                 } else {
-                    if intersection.is_empty() {
-                        continue; // Correct: no roles
-                    }
+                    let mut failed = false;
 
-                    total_error += 1;
+                    if !remove.is_empty() {
+                        if let Err(ex) = member.remove_roles(&cache_http, &remove).await {
+                            error!("Failed to remove roles: {}", ex);
+                            failed = true;
+                        }
+                    }
 
-                    if option_remove.unwrap_or(false) {
-                        for r in intersection {
-                            if let Err(ex) = member.remove_role(&ctx, r).await {
-                                error!("Failed to remove role: {}", ex);
-                                count_error += 1;
-                            } else {
-                                count_remove += 1;
-                            }
+                    if !add.is_empty() {
+                        if let Err(ex) = member.add_roles(&cache_http, &add).await {
+                            error!("Failed to add roles: {}", ex);
+                            failed = true;
                         }
                     }
+
+                    if failed {
+                        report.count_error += 1;
+                    } else {
+                        report.count(kind);
+                    }
                 }
+
+                report.fixes.push(RoleFix {
+                    user: u.user,
+                    name: member.user.tag(),
+                    kind,
+                    add,
+                    remove
+                });
             }
         }
-
-        discord_message.edit(ctx, |m| {
-            m.embeds.clear();
-            m.embed(|e| e
-                .title("Role Auto-fix")
-                .description(format!("Processed {total} members in the database with {total_error} errors found:\n\
-            - Trivial fixes: {count_trivial}\n\
-            - Fixes for multiple roles: {count_multiple}\n\
-            - Members with their roles fully revoked: {count_remove}\n\
-            - Members demoted: {count_demote}\n\
-            - Errors adding/removing roles: {count_error}"))
-            )
-        }).await?;
-    } else {
-        ctx.say("This command can only be run in a server.").await?;
     }
 
-    Ok(())
+    Ok(report)
+}
+
+// Every rank role a member at the given level should have when stacking.
+fn stacked_roles(roles: &[Rank], level: i32) -> HashSet<RoleId> {
+    roles.iter()
+        .filter(|r| r.min_level <= level)
+        .filter_map(|r| r.role_id)
+        .collect()
 }
\ No newline at end of file
@@ -0,0 +1,119 @@
+use crate::{CowContext, cowdb, Error};
+use serenity::model::id::{RoleId, UserId};
+use crate::{Database, db};
+use tracing::{error};
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("exclude_user", "exclude_role", "exclude_list"),
+    guild_only,
+    discard_spare_arguments,
+    description_localized("en-US", "Manage users and roles that can't earn experience or appear on the leaderboard."),
+)]
+pub async fn exclude(ctx: CowContext<'_>) -> Result<(), Error> {
+    list_exclusions(ctx).await
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "user",
+    description_localized("en-US", "Exclude or include a user in experience and the leaderboard."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn exclude_user(
+    ctx: CowContext<'_>,
+    #[description = "The user to toggle the exclusion of"] user: UserId)
+-> Result<(), Error> {
+    toggle(ctx, user.0, false, format!("<@{user}>")).await
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "role",
+    description_localized("en-US", "Exclude or include members with a role in experience and the leaderboard."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn exclude_role(
+    ctx: CowContext<'_>,
+    #[description = "The role to toggle the exclusion of"] role: RoleId)
+-> Result<(), Error> {
+    toggle(ctx, role.0, true, format!("<@&{role}>")).await
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "list",
+    description_localized("en-US", "List the users and roles excluded from experience and the leaderboard."),
+    discard_spare_arguments
+)]
+pub async fn exclude_list(ctx: CowContext<'_>) -> Result<(), Error> {
+    list_exclusions(ctx).await
+}
+
+async fn toggle(ctx: CowContext<'_>, target_id: u64, is_role: bool, mention: String) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.toggle_exclusion(guild_id, target_id, is_role).await {
+            Ok(excluded) => {
+                let content = if excluded {
+                    format!("Excluded {mention} from experience and the leaderboard.")
+                } else {
+                    format!("{mention} can earn experience and appear on the leaderboard again.")
+                };
+
+                ctx.send(|m| m.content(content).allowed_mentions(|o| o.empty_users().empty_parse().empty_roles())).await?;
+            }
+            Err(ex) => {
+                error!("Failed to toggle exclusion: {}", ex);
+                ctx.say("Failed to update the exclusions for this server.").await?;
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+async fn list_exclusions(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_exclusions(guild_id).await {
+            Ok(exclusions) => {
+                let users = exclusions.users.iter()
+                    .map(|u| format!("<@{u}>"))
+                    .reduce(|a, b| format!("{a}\n{b}"))
+                    .unwrap_or_else(|| "None".to_string());
+
+                let roles = exclusions.roles.iter()
+                    .map(|r| format!("<@&{r}>"))
+                    .reduce(|a, b| format!("{a}\n{b}"))
+                    .unwrap_or_else(|| "None".to_string());
+
+                ctx.send(|m| {
+                    m.embeds.clear();
+                    m.embed(|e| e
+                        .title("Excluded from Experience")
+                        .field("Users", users, true)
+                        .field("Roles", roles, true)
+                    )
+                }).await?;
+            }
+            Err(ex) => {
+                error!("Failed to get exclusions: {}", ex);
+                ctx.say("Failed to get the exclusions for this server.").await?;
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
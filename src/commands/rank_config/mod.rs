@@ -1,12 +1,16 @@
 mod roles;
 mod diagnostics;
+mod exclusions;
+pub mod autofix;
 
 use roles::*;
 use diagnostics::*;
+use exclusions::*;
+use autofix::autofix;
 use crate::{CowContext, Error};
 
 #[poise::command(prefix_command, slash_command,
-    subcommands("list", "add", "remove", "scan", "fix"),
+    subcommands("list", "add", "remove", "rolemode", "scan", "fix", "exclude", "autofix"),
     discard_spare_arguments,
     description_localized("en-US", "Configuration to manage ranks and levelling on the server."),
     aliases("rc"),
@@ -81,6 +81,58 @@ pub async fn remove(
     Ok(())
 }
 
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Choose whether members keep every rank role they earn, or only their highest."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn rolemode(
+    ctx: CowContext<'_>,
+    #[description = "Either \"stack\" to keep every earned rank, or \"replace\" to keep only the highest."] mode: Option<String>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let stack = match mode.as_deref().map(|m| m.to_lowercase()) {
+            None => {
+                match db.get_role_stacking(guild_id).await {
+                    Ok(true) => { ctx.say("Members currently keep every rank they earn (stack).").await?; }
+                    Ok(false) => { ctx.say("Members currently only keep their highest rank (replace).").await?; }
+                    Err(ex) => {
+                        error!("Failed to get role stacking mode: {}", ex);
+                        ctx.say("Failed to get the rank role mode for this server.").await?;
+                    }
+                }
+
+                return Ok(());
+            }
+            Some(m) if m == "stack" => true,
+            Some(m) if m == "replace" => false,
+            Some(_) => {
+                ctx.say("The mode must be either `stack` or `replace`.").await?;
+                return Ok(());
+            }
+        };
+
+        match db.set_role_stacking(guild_id, stack).await {
+            Ok(_) => {
+                ctx.say(format!("Members will now {}. You may want to run `rankconfig fix` to update existing members.",
+                    if stack { "keep every rank they earn" } else { "only keep their highest rank" })).await?;
+            }
+            Err(ex) => {
+                error!("Failed to set role stacking mode: {}", ex);
+                ctx.say("Failed to set the rank role mode for this server.").await?;
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
 #[poise::command(
     prefix_command,
     slash_command,
@@ -96,6 +148,7 @@ pub async fn list(ctx: CowContext<'_>) -> Result<(), Error> {
 pub async fn list_code(ctx: CowContext<'_>) -> Result<(), Error> {
     let db = cowdb!(ctx);
     if let Some(guild_id) = ctx.guild_id() {
+        let stack = db.get_role_stacking(guild_id).await.unwrap_or_default();
         match db.get_roles(guild_id).await {
             Ok(items) => {
                 if let Err(ex) = ctx.send(|m| {
@@ -113,7 +166,9 @@ pub async fn list_code(ctx: CowContext<'_>) -> Result<(), Error> {
                                     })
                                     .reduce(|a, b| {format!("{a}\n{b}")})
                                     .unwrap_or_else(|| "No roles are registered on this server.".to_string())
-                        )})}).await {
+                            )
+                            .footer(|f| f.text(if stack { "Mode: stack" } else { "Mode: replace" }))
+                    })}).await {
                     error!("Failed to send message to server: {}", ex);
                 }
             },
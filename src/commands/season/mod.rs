@@ -0,0 +1,96 @@
+mod season_config;
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tracing::{error, info};
+use serenity::{
+    CacheAndHttp,
+    http::Http,
+    model::id::{GuildId, RoleId},
+    prelude::TypeMap
+};
+use tokio::sync::RwLock;
+use tokio::time;
+use crate::{CowContext, Database, Error};
+use crate::models::db_models::{ClosedSeason, SeasonConfig};
+use crate::services::message_handler::sync_rank_role;
+use season_config::*;
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("info", "close", "schedule", "keep", "champion"),
+    discard_spare_arguments,
+    description_localized("en-US", "Commands for running experience seasons with archived leaderboards."),
+    guild_only,
+    identifying_name = "Seasons"
+)]
+pub async fn season(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+// Archives the leaderboard, shrinks everyone's experience, then fixes up rank and champion roles.
+pub async fn end_season(db: &Database, http: &Http, guild_id: GuildId, config: &SeasonConfig) -> Result<ClosedSeason, Error> {
+    let users = db.get_users(guild_id).await?;
+    let closed = db.close_season(guild_id, config.keep_percent).await?;
+
+    for u in users {
+        let new_level = u.exp.level * config.keep_percent.max(0) / 100;
+        // Members who left the server can't have their roles updated anyway.
+        if let Err(ex) = sync_rank_role(db, http, guild_id, u.user, u.exp.level, new_level).await {
+            info!("Skipped updating roles for {} after closing season: {}", u.user, ex);
+        }
+    }
+
+    if let Some(role) = config.champion_role.map(RoleId::from) {
+        if let Some(previous) = closed.previous_champion.filter(|p| closed.champion != Some(*p)) {
+            if let Ok(mut member) = guild_id.member(http, previous).await {
+                if let Err(ex) = member.remove_role(http, role).await {
+                    error!("Failed to remove champion role: {}", ex);
+                }
+            }
+        }
+
+        if let Some(champion) = closed.champion {
+            if let Ok(mut member) = guild_id.member(http, champion).await {
+                if let Err(ex) = member.add_role(http, role).await {
+                    error!("Failed to add champion role: {}", ex);
+                }
+            }
+        }
+    }
+
+    Ok(closed)
+}
+
+pub async fn check_seasons(data: Arc<RwLock<TypeMap>>, ctx: Arc<CacheAndHttp>) {
+    let mut interval_min = time::interval(Duration::from_secs(10 * 60));
+    loop {
+        interval_min.tick().await;
+        let db = {
+            let ctx_global = data.read().await;
+            ctx_global.get::<Database>().expect("Couldn't find database").clone()
+        };
+
+        match db.get_due_seasons().await {
+            Ok(configs) => {
+                for mut config in configs {
+                    let guild_id = GuildId::from(config.id);
+                    match end_season(&db, &ctx.http, guild_id, &config).await {
+                        Ok(closed) => info!("Closed season {} for server {}", closed.season, guild_id),
+                        Err(ex) => error!("Failed to close season for server {}: {}", guild_id, ex)
+                    }
+
+                    // Schedule the next season even if this one failed, so we don't retry every tick.
+                    let days = config.interval_days.unwrap_or(1).max(1) as i64;
+                    config.next_close = Some(Utc::now().naive_utc() + chrono::Duration::days(days));
+                    if let Err(ex) = db.update_season_config(&config).await {
+                        error!("Failed to schedule the next season: {}", ex);
+                    }
+                }
+            },
+            Err(ex) => {
+                error!("Failed to query due seasons: {}", ex);
+            }
+        }
+    }
+}
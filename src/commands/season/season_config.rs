@@ -0,0 +1,233 @@
+use chrono::Utc;
+use tracing::error;
+use serenity::model::guild::Role;
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::commands::season::end_season;
+use crate::util::confirm;
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "Get the current settings for experience seasons."),
+    guild_only,
+    discard_spare_arguments
+)]
+pub async fn info(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+pub async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Ok(config) = db.get_season_config(guild_id).await {
+            let schedule = match (config.interval_days, config.next_close) {
+                (Some(days), Some(next)) => format!("Every {days} days, next closing <t:{}:R>", next.and_utc().timestamp()),
+                _ => "Manual".to_string()
+            };
+
+            let kept = if config.keep_percent <= 0 {
+                "Full reset".to_string()
+            } else {
+                format!("{}% kept", config.keep_percent)
+            };
+
+            ctx.send(|m| {
+                m.embeds.clear();
+                m.embed(|e|
+                    e
+                        .title("Season Settings")
+                        .field("Schedule", schedule, true)
+                        .field("Experience", kept, true)
+                        .field("Champion Role", config.champion_role.map(|o| format!("<@&{o}>")).unwrap_or_else(|| "No Champion Role".to_string()), true)
+                )
+            }).await?;
+        } else {
+            ctx.say("Failed to fetch season settings for this server...").await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Close the current season, archiving the leaderboard and resetting experience."),
+    required_permissions = "ADMINISTRATOR",
+    guild_cooldown = "60",
+    discard_spare_arguments
+)]
+pub async fn close(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let config = db.get_season_config(guild_id).await?;
+
+        if !confirm(&ctx, "This will archive the current leaderboard and reset everyone's experience. Are you sure?").await? {
+            return Ok(());
+        }
+
+        let discord_message = ctx.send(|m| {
+            m.embeds.clear();
+            m.embed(|e| e
+                .title("Season Closing")
+                .description("Now archiving the leaderboard and updating roles, please wait warmly...")
+            )
+        }).await?;
+
+        match end_season(&db, &ctx.serenity_context().http, guild_id, &config).await {
+            Ok(closed) => {
+                let champion = closed.champion.map(|o| format!("Congratulations to our champion, <@{o}>!")).unwrap_or_else(|| "Nobody placed this season.".to_string());
+                discord_message.edit(ctx, |m| {
+                    m.embeds.clear();
+                    m.embed(|e| e
+                        .title(format!("Season {} Closed", closed.season))
+                        .description(format!("{champion}\nYou can view the final standings with `levels` and `rank` using season {}.", closed.season))
+                    )
+                }).await?;
+            }
+            Err(ex) => {
+                error!("Failed to close season: {}", ex);
+                discord_message.edit(ctx, |m| {
+                    m.embeds.clear();
+                    m.embed(|e| e
+                        .title("Season Closing")
+                        .description("We couldn't close the season, sorry... Try again later?")
+                    )
+                }).await?;
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Automatically close seasons every given number of days."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn schedule(
+    ctx: CowContext<'_>,
+    #[description = "The length of a season in days; leave empty to only close seasons manually."] #[min = 1] days: Option<i32>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_season_config(guild_id).await {
+            Ok(mut config) => {
+                let days = days.filter(|d| *d > 0);
+                config.interval_days = days;
+                config.next_close = days.map(|d| Utc::now().naive_utc() + chrono::Duration::days(d as i64));
+
+                if let Err(ex) = db.update_season_config(&config).await {
+                    ctx.say("We couldn't update the season settings, sorry... Try again later?").await?;
+                    error!("Failed to update season settings: {}", ex);
+                } else if let Some(next) = config.next_close {
+                    ctx.say(format!("Seasons will now close every {} days, starting <t:{}:R>.", days.unwrap(), next.and_utc().timestamp())).await?;
+                } else {
+                    ctx.say("Seasons will now only be closed manually.").await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the season settings... try again later?").await?;
+                error!("Failed to get season settings: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set how much experience members keep when a season closes."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn keep(
+    ctx: CowContext<'_>,
+    #[description = "A percentage from 0 (full reset) to 100."] #[min = 0] #[max = 100] percent: i32)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if !(0..=100).contains(&percent) {
+        ctx.say("The percentage must be between 0 and 100.").await?;
+        return Ok(())
+    }
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_season_config(guild_id).await {
+            Ok(mut config) => {
+                config.keep_percent = percent;
+
+                if let Err(ex) = db.update_season_config(&config).await {
+                    ctx.say("We couldn't update the season settings, sorry... Try again later?").await?;
+                    error!("Failed to update season settings: {}", ex);
+                } else if percent == 0 {
+                    ctx.say("Experience will be fully reset when a season closes.").await?;
+                } else {
+                    ctx.say(format!("Members will keep {percent}% of their levels and experience when a season closes.")).await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the season settings... try again later?").await?;
+                error!("Failed to get season settings: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set a role to give to the top member when a season closes."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn champion(
+    ctx: CowContext<'_>,
+    #[description = "The champion role; leave empty to disable."] role: Option<Role>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_season_config(guild_id).await {
+            Ok(mut config) => {
+                config.champion_role = role.as_ref().map(|r| r.id.0);
+
+                if let Err(ex) = db.update_season_config(&config).await {
+                    ctx.say("We couldn't update the season settings, sorry... Try again later?").await?;
+                    error!("Failed to update season settings: {}", ex);
+                } else if let Some(role) = role {
+                    ctx.say(format!("Season champions will now receive <@&{}>.", role.id)).await?;
+                } else {
+                    ctx.say("Season champions will no longer receive a role.").await?;
+                }
+            }
+            Err(ex) => {
+                ctx.say("We couldn't get the season settings... try again later?").await?;
+                error!("Failed to get season settings: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
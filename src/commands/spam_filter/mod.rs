@@ -0,0 +1,15 @@
+mod spam_filter_config;
+
+use spam_filter_config::*;
+use crate::{CowContext, Error};
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("info", "toggle", "minlength", "duplicates", "emoji", "stickers", "burst", "stats", "resetstats"),
+    discard_spare_arguments,
+    description_localized("en-US", "Commands for configuring the spam filter for chat xp."),
+    guild_only,
+    identifying_name = "Spam Filter"
+)]
+pub async fn spamfilter(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
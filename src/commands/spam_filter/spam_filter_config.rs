@@ -0,0 +1,301 @@
+use tracing::error;
+use serenity::model::id::GuildId;
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::models::db_models::SpamFilterConfig;
+use crate::services::spam_filter::SpamFilterCache;
+use crate::util::{to_ms, from_ms};
+
+// Every setter is the same: fetch, tweak one thing, save.
+async fn update_config(ctx: &CowContext<'_>, guild_id: GuildId, success: &str, modify: impl FnOnce(&mut SpamFilterConfig)) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    match db.get_spam_filter_config(guild_id).await {
+        Ok(mut config) => {
+            modify(&mut config);
+
+            if let Err(ex) = db.update_spam_filter_config(&config).await {
+                ctx.say("We couldn't update the spam filter, sorry... Try again later?").await?;
+                error!("Failed to update spam filter: {}", ex);
+            } else {
+                let cache = {
+                    let data = ctx.serenity_context().data.read().await;
+                    data.get::<SpamFilterCache>().unwrap().clone()
+                };
+                cache.lock().unwrap().insert(guild_id, config);
+
+                ctx.say(success).await?;
+            }
+        }
+        Err(ex) => {
+            ctx.say("We couldn't get the spam filter settings... try again later?").await?;
+            error!("Failed to get spam filter: {}", ex);
+        }
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    description_localized("en-US", "Get the current settings for the spam filter."),
+    guild_only,
+    discard_spare_arguments
+)]
+pub async fn info(ctx: CowContext<'_>) -> Result<(), Error> {
+    info_code(ctx).await
+}
+
+pub async fn info_code(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Ok(config) = db.get_spam_filter_config(guild_id).await {
+            let duplicates = if config.duplicate_threshold > 0 {
+                format!("{}% similar", config.duplicate_threshold)
+            } else {
+                "Allowed".to_string()
+            };
+
+            let burst = if config.burst_limit > 0 {
+                format!("{} messages per {}", config.burst_limit, from_ms(config.burst_window as u64))
+            } else {
+                "Unlimited".to_string()
+            };
+
+            ctx.send(|m| {
+                m.embeds.clear();
+                m.embed(|e|
+                    e
+                        .title("Spam Filter Settings")
+                        .field("Status", if config.enabled { "Enabled" } else { "Disabled" }, true)
+                        .field("Minimum Length", config.min_length, true)
+                        .field("Duplicates", duplicates, true)
+                        .field("Emoji-only", if config.block_emoji_only { "Blocked" } else { "Allowed" }, true)
+                        .field("Sticker-only", if config.block_sticker_only { "Blocked" } else { "Allowed" }, true)
+                        .field("Burst Limit", burst, true)
+                )
+            }).await?;
+        } else {
+            ctx.say("Failed to fetch spam filter settings for this server...").await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Enable/disable the spam filter for chat xp."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn toggle(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        let enabled = !db.get_spam_filter_config(guild_id).await.map(|c| c.enabled).unwrap_or(false);
+        let success = if enabled { "Enabled the spam filter." } else { "Disabled the spam filter." };
+        update_config(&ctx, guild_id, success, |c| c.enabled = enabled).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set the minimum message length to earn xp."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn minlength(
+    ctx: CowContext<'_>,
+    #[description = "The minimum amount of characters; 0 allows anything."] #[min = 0] #[max = 2000] length: i32)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        update_config(&ctx, guild_id, "Successfully updated the minimum message length!", |c| c.min_length = length.max(0)).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set how similar a message can be to someone's recent messages before it's a duplicate."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn duplicates(
+    ctx: CowContext<'_>,
+    #[description = "A similarity percentage from 1 to 100; 0 allows duplicates."] #[min = 0] #[max = 100] threshold: i32)
+-> Result<(), Error> {
+    if !(0..=100).contains(&threshold) {
+        ctx.say("The percentage must be between 0 and 100.").await?;
+        return Ok(())
+    }
+
+    if let Some(guild_id) = ctx.guild_id() {
+        update_config(&ctx, guild_id, "Successfully updated the duplicate threshold!", |c| c.duplicate_threshold = threshold).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set whether messages with only emoji earn xp."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn emoji(
+    ctx: CowContext<'_>,
+    #[description = "Whether to block emoji-only messages."] block: bool)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let success = if block { "Emoji-only messages will no longer earn xp." } else { "Emoji-only messages will now earn xp." };
+        update_config(&ctx, guild_id, success, |c| c.block_emoji_only = block).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Set whether messages with only a sticker earn xp."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn stickers(
+    ctx: CowContext<'_>,
+    #[description = "Whether to block sticker-only messages."] block: bool)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let success = if block { "Sticker-only messages will no longer earn xp." } else { "Sticker-only messages will now earn xp." };
+        update_config(&ctx, guild_id, success, |c| c.block_sticker_only = block).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Limit how many messages a user can send across all channels in a time window."),
+    required_permissions = "ADMINISTRATOR"
+)]
+pub async fn burst(
+    ctx: CowContext<'_>,
+    #[description = "The maximum amount of messages; 0 disables the limit."] #[min = 0] limit: i32,
+    #[description = "A duration with suffixes d, h, m, and s. Ex. \"10s\" for 10 seconds."] window: Option<String>)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        let window = match window.map(to_ms) {
+            Some(Some(window)) => Some(window),
+            Some(None) => {
+                ctx.say("The window must be in the form #d#h#m#s.").await?;
+                return Ok(());
+            }
+            None => None
+        };
+
+        update_config(&ctx, guild_id, "Successfully updated the burst limit!", |c| {
+            c.burst_limit = limit.max(0);
+            if let Some(window) = window {
+                c.burst_window = window;
+            }
+        }).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Show how many messages the spam filter has caught."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn stats(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        match db.get_spam_counters(guild_id).await {
+            Ok(counters) => {
+                let reasons = counters.reasons.iter()
+                    .map(|(reason, count)| format!("{reason}: {count}"))
+                    .reduce(|a, b| format!("{a}\n{b}"))
+                    .unwrap_or_else(|| "Nothing caught yet.".to_string());
+
+                let offenders = counters.offenders.iter()
+                    .map(|(user, count)| format!("<@{user}>: {count}"))
+                    .reduce(|a, b| format!("{a}\n{b}"))
+                    .unwrap_or_else(|| "Nobody yet.".to_string());
+
+                ctx.send(|m| {
+                    m.embeds.clear();
+                    m.embed(|e| e
+                        .title("Spam Filter Statistics")
+                        .field("Caught Messages", reasons, true)
+                        .field("Top Offenders", offenders, true)
+                    )
+                }).await?;
+            }
+            Err(ex) => {
+                ctx.say("Failed to get spam filter statistics.").await?;
+                error!("Failed to get spam counters: {}", ex);
+            }
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    description_localized("en-US", "Reset the spam filter statistics."),
+    required_permissions = "ADMINISTRATOR",
+    discard_spare_arguments
+)]
+pub async fn resetstats(ctx: CowContext<'_>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Err(ex) = db.reset_spam_counters(guild_id).await {
+            ctx.say("Failed to reset spam filter statistics.").await?;
+            error!("Failed to reset spam counters: {}", ex);
+        } else {
+            ctx.say("Reset the spam filter statistics.").await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
@@ -4,9 +4,9 @@ use timeout_config::*;
 use crate::{CowContext, Error};
 
 #[poise::command(prefix_command, slash_command,
-    subcommands("get", "set"),
+    subcommands("get", "set", "channel", "role"),
     discard_spare_arguments,
-    description_localized("en-US", "Commands for viewing and settinge the cooldown for chat xp."),
+    description_localized("en-US", "Commands for viewing and setting the cooldown for chat xp."),
     identifying_name = "Leveling Timeout"
 )]
 pub async fn timeout(ctx: CowContext<'_>) -> Result<(), Error> {
@@ -1,5 +1,7 @@
 use tracing::error;
+use serenity::model::id::{ChannelId, GuildId, RoleId};
 use crate::{CowContext, Database, db, cowdb, Error};
+use crate::models::db_models::TimeoutScope;
 use crate::util::{ to_ms, from_ms };
 
 #[poise::command(
@@ -49,16 +51,129 @@ pub async fn get(ctx: CowContext<'_>) -> Result<(), Error> {
 pub async fn get_code(ctx: CowContext<'_>) -> Result<(), Error> {
     let db = cowdb!(ctx);
     if let Some(server_id) = ctx.guild_id() {
-        match db.get_timeout(server_id).await {
-            Ok(timeout) => { ctx.say(format!("The timeout is {}.", from_ms(timeout as u64))).await?; }
+        let timeout = match db.get_timeout(server_id).await {
+            Ok(timeout) => timeout,
             Err(err) => {
                 ctx.say("Could not get timeout.").await?;
                 error!("Could not get timeout: {}", err);
+                return Ok(());
             }
+        };
+
+        let overrides = match db.get_timeout_overrides(server_id).await {
+            Ok(overrides) => overrides,
+            Err(err) => {
+                ctx.say("Could not get timeout overrides.").await?;
+                error!("Could not get timeout overrides: {}", err);
+                return Ok(());
+            }
+        };
+
+        let list = |scope: TimeoutScope, mention: &str| overrides.iter()
+            .filter(|o| o.scope == scope)
+            .map(|o| format!("<{}{}>: {}", mention, o.id, from_ms(o.timeout as u64)))
+            .reduce(|a, b| format!("{a}\n{b}"))
+            .unwrap_or_else(|| "None".to_string());
+
+        let channels = list(TimeoutScope::Channel, "#");
+        let roles = list(TimeoutScope::Role, "@&");
+
+        ctx.send(|m| {
+            m.embeds.clear();
+            m.embed(|e| e
+                .title("Experience Cooldowns")
+                .description("Role overrides take priority over channel overrides; if a member has several, the shortest applies.")
+                .field("Server-wide", from_ms(timeout as u64), false)
+                .field("Channels", channels, true)
+                .field("Roles", roles, true)
+            )
+        }).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    description_localized("en-US", "Overrides the cooldown for messaging xp gain in a channel."),
+)]
+pub async fn channel(
+    ctx: CowContext<'_>,
+    #[description = "The channel to override the cooldown for."] channel: ChannelId,
+    #[description = "A duration with suffixes d, h, m, and s; leave empty to remove the override."] timeout: Option<String>)
+-> Result<(), Error> {
+    if let Some(server_id) = ctx.guild_id() {
+        if !ctx.guild().map(|g| g.channels.contains_key(&channel)).unwrap_or(false) {
+            ctx.say("Could not find channel in this server!").await?;
+            return Ok(())
         }
+
+        set_override(&ctx, server_id, TimeoutScope::Channel, channel.0, &format!("<#{channel}>"), timeout).await?;
     } else {
         ctx.say("This command can only be run in a server.").await?;
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    description_localized("en-US", "Overrides the cooldown for messaging xp gain for members with a role."),
+)]
+pub async fn role(
+    ctx: CowContext<'_>,
+    #[description = "The role to override the cooldown for."] role: RoleId,
+    #[description = "A duration with suffixes d, h, m, and s; leave empty to remove the override."] timeout: Option<String>)
+-> Result<(), Error> {
+    if let Some(server_id) = ctx.guild_id() {
+        if !ctx.guild().map(|g| g.roles.contains_key(&role)).unwrap_or(false) {
+            ctx.say("Could not find role in this server!").await?;
+            return Ok(())
+        }
+
+        set_override(&ctx, server_id, TimeoutScope::Role, role.0, &format!("<@&{role}>"), timeout).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+async fn set_override(ctx: &CowContext<'_>, server_id: GuildId, scope: TimeoutScope, id: u64, mention: &str, timeout: Option<String>) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    let timeout = match timeout.map(to_ms) {
+        Some(Some(timeout)) => Some(timeout),
+        Some(None) => {
+            ctx.say("The timeout must be in the form #d#h#m#s.").await?;
+            return Ok(());
+        }
+        None => None
+    };
+
+    match db.set_timeout_override(server_id, scope, id, timeout).await {
+        Ok(changed) => {
+            let reply = match timeout {
+                Some(timeout) => format!("Set the timeout for {} to {}.", mention, from_ms(timeout as u64)),
+                None if changed => format!("Removed the timeout override for {mention}."),
+                None => format!("{mention} doesn't have a timeout override.")
+            };
+
+            ctx.send(|m| m.content(reply).allowed_mentions(|o| o.empty_users().empty_parse().empty_roles())).await?;
+        }
+        Err(err) => {
+            ctx.say("Could not set timeout override.").await?;
+            error!("Could not set timeout override: {}", err);
+        }
+    }
+
+    Ok(())
+}
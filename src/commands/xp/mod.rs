@@ -0,0 +1,16 @@
+mod xp_config;
+
+use xp_config::*;
+use crate::{CowContext, Error};
+
+#[poise::command(prefix_command, slash_command,
+    subcommands("give", "take", "set", "reset"),
+    discard_spare_arguments,
+    description_localized("en-US", "Commands for correcting the experience of members."),
+    guild_only,
+    identifying_name = "Experience Management"
+)]
+pub async fn xp(ctx: CowContext<'_>) -> Result<(), Error> {
+    ctx.send(|m| m.ephemeral(true).content("Please invoke a subcommand. To list them, try `!help xp` or `/help xp`.")).await?;
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use tracing::error;
+use crate::{CowContext, Database, db, cowdb, Error};
+use crate::models::db_models::{Experience, ExperienceChange};
+use crate::services::message_handler::{sync_rank_role, update_rank_role, update_stacked_ranks};
+use crate::util::confirm;
+
+// Saves the change to a user's experience, then moves their rank role to match.
+async fn apply_experience(ctx: &CowContext<'_>, guild_id: GuildId, user_id: UserId, change: ExperienceChange) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    let (old, new) = db.change_xp(guild_id, user_id, change).await?;
+
+    let mut content = format!("<@{user_id}> is now level {} with {} xp (previously level {} with {} xp).", new.level, new.xp, old.level, old.xp);
+
+    match sync_rank_role(&db, &ctx.serenity_context().http, guild_id, user_id, old.level, new.level).await {
+        Ok(true) => {}
+        Ok(false) => {
+            content += "\n(We failed to update their roles; maybe we don't have permission?)";
+        }
+        Err(ex) => {
+            content += "\n(We couldn't update their roles; are they still in the server?)";
+            error!("Failed to sync rank role: {}", ex);
+        }
+    }
+
+    ctx.send(|m| {
+        m.embeds.clear();
+        m.embed(|e| e
+            .title("Experience Updated")
+            .description(content)
+        )
+    }).await?;
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    description_localized("en-US", "Give experience or levels to a user.")
+)]
+pub async fn give(
+    ctx: CowContext<'_>,
+    #[description = "The user to give experience to"] user: UserId,
+    #[description = "The amount of experience (or levels) to give"] #[min = 1] #[max = 1000000] amount: i32,
+    #[description = "Treat the amount as levels instead of experience"] levels: Option<bool>)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        if amount <= 0 {
+            ctx.say("The given number must be positive.").await?;
+            return Ok(());
+        }
+
+        let change = if levels.unwrap_or(false) {
+            Experience { level: amount, xp: 0 }
+        } else {
+            Experience { level: 0, xp: amount }
+        };
+
+        apply_experience(&ctx, guild_id, user, ExperienceChange::Add(change)).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    description_localized("en-US", "Take experience or levels away from a user.")
+)]
+pub async fn take(
+    ctx: CowContext<'_>,
+    #[description = "The user to take experience from"] user: UserId,
+    #[description = "The amount of experience (or levels) to take"] #[min = 1] #[max = 1000000] amount: i32,
+    #[description = "Treat the amount as levels instead of experience"] levels: Option<bool>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if amount <= 0 {
+            ctx.say("The given number must be positive.").await?;
+            return Ok(());
+        }
+
+        let levels = levels.unwrap_or(false);
+        let old = db.get_xp(guild_id, user).await?;
+        let unit = if levels { "levels" } else { "xp" };
+        if !confirm(&ctx, &format!("This will take {amount} {unit} from <@{user}> (level {}, {} xp). Are you sure?", old.level, old.xp)).await? {
+            return Ok(());
+        }
+
+        let change = if levels {
+            Experience { level: -amount, xp: 0 }
+        } else {
+            Experience { level: 0, xp: -amount }
+        };
+
+        apply_experience(&ctx, guild_id, user, ExperienceChange::Add(change)).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    description_localized("en-US", "Set the experience and level of a user.")
+)]
+pub async fn set(
+    ctx: CowContext<'_>,
+    #[description = "The user to modify"] user: UserId,
+    #[description = "The new level of the user"] #[min = 0] level: i32,
+    #[description = "The new experience of the user within their level, 0 by default"] #[min = 0] xp: Option<i32>)
+-> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if let Some(guild_id) = ctx.guild_id() {
+        if level < 0 || xp.unwrap_or(0) < 0 {
+            ctx.say("The given numbers must be positive or zero.").await?;
+            return Ok(());
+        }
+
+        let xp = xp.unwrap_or(0);
+        let old = db.get_xp(guild_id, user).await?;
+        if !confirm(&ctx, &format!("This will set <@{user}> (level {}, {} xp) to level {level} with {xp} xp. Are you sure?", old.level, old.xp)).await? {
+            return Ok(());
+        }
+
+        apply_experience(&ctx, guild_id, user, ExperienceChange::Set(Experience { level, xp })).await?;
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    description_localized("en-US", "Reset the experience of a user, or everyone on the server if no user is given."),
+    guild_cooldown = "60"
+)]
+pub async fn reset(
+    ctx: CowContext<'_>,
+    #[description = "The user to reset; leave empty to reset the whole server"] user: Option<UserId>)
+-> Result<(), Error> {
+    if let Some(guild_id) = ctx.guild_id() {
+        if let Some(user_id) = user {
+            reset_user(&ctx, guild_id, user_id).await?;
+        } else {
+            reset_guild(&ctx, guild_id).await?;
+        }
+    } else {
+        ctx.say("This command can only be run in a server.").await?;
+    }
+
+    Ok(())
+}
+
+async fn reset_user(ctx: &CowContext<'_>, guild_id: GuildId, user_id: UserId) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    let old = db.get_xp(guild_id, user_id).await?;
+    if !confirm(ctx, &format!("This will reset <@{user_id}> (level {}, {} xp) back to zero. Are you sure?", old.level, old.xp)).await? {
+        return Ok(());
+    }
+
+    if !db.reset_xp(guild_id, user_id).await? {
+        ctx.say("This user did not have any experience to reset.").await?;
+        return Ok(());
+    }
+
+    let mut content = format!("Reset the experience of <@{user_id}>.");
+
+    match sync_rank_role(&db, &ctx.serenity_context().http, guild_id, user_id, old.level, 0).await {
+        Ok(true) => {}
+        Ok(false) => {
+            content += "\n(We failed to update their roles; maybe we don't have permission?)";
+        }
+        Err(ex) => {
+            content += "\n(We couldn't update their roles; are they still in the server?)";
+            error!("Failed to sync rank role: {}", ex);
+        }
+    }
+
+    ctx.say(content).await?;
+
+    Ok(())
+}
+
+async fn reset_guild(ctx: &CowContext<'_>, guild_id: GuildId) -> Result<(), Error> {
+    let db = cowdb!(ctx);
+
+    if !confirm(ctx, "This will reset the experience of **everyone** on this server, and cannot be undone. Are you sure?").await? {
+        return Ok(());
+    }
+
+    let discord_message = ctx.send(|m| {
+        m.embeds.clear();
+        m.embed(|e| e
+            .title("Experience Reset")
+            .description("Now resetting experience and roles, please wait warmly...")
+        )
+    }).await?;
+
+    // Grab everyone's roles before we wipe the table, so we know what to take away.
+    let stack = db.get_role_stacking(guild_id).await?;
+    let roles = db.get_roles(guild_id).await?;
+    let role_set = roles.iter().filter_map(|r| r.role_id).collect::<HashSet<RoleId>>();
+    let users = db.get_users(guild_id).await?;
+    let total = db.reset_server_xp(guild_id).await?;
+    let base_rank = db.get_highest_role(guild_id, 0).await?;
+
+    let mut count_error = 0;
+    for u in users {
+        if let Ok(mut member) = guild_id.member(ctx, u.user).await {
+            if stack {
+                if !update_stacked_ranks(&ctx.serenity_context().http, &mut member, &roles, 0).await {
+                    count_error += 1;
+                }
+
+                continue;
+            }
+
+            let excess = member.roles.iter()
+                .filter(|r| role_set.contains(r) && Some(**r) != base_rank && Some(**r) != u.role_id)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let mut success = update_rank_role(&ctx.serenity_context().http, &mut member, u.role_id, base_rank).await;
+            for r in excess {
+                if let Err(ex) = member.remove_role(ctx, r).await {
+                    error!("Failed to remove role: {}", ex);
+                    success = false;
+                }
+            }
+
+            if !success {
+                count_error += 1;
+            }
+        }
+    }
+
+    let mut content = format!("Reset the experience of {total} members.");
+    if count_error > 0 {
+        content += &format!("\n(We failed to update the roles of {count_error} members; maybe we don't have permission?)");
+    }
+
+    discord_message.edit(*ctx, |m| {
+        m.embeds.clear();
+        m.embed(|e| e
+            .title("Experience Reset")
+            .description(content)
+        )
+    }).await?;
+
+    Ok(())
+}
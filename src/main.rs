@@ -3,19 +3,19 @@ mod commands;
 mod services;
 mod util;
 
-use std::collections::{HashSet};
-use commands::{get_framework};
+use std::collections::{HashMap, HashSet};
+use commands::{get_framework, cowboard::cowboard_handler::CowboardQueue, music::{music_filters::MusicFilters, music_handler::MusicLoops}};
 use models::config::Config;
-use services::{*, database::Database};
+use services::{*, database::Database, spam_filter::{SpamFilterCache, SpamTracker}};
 use std::fs;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::env;
 use std::error;
-use lavalink_rs::{LavalinkClient, gateway::LavalinkEventHandler};
+use lavalink_rs::{LavalinkClient, gateway::LavalinkEventHandler, model::{TrackFinish, TrackStart}};
 use serenity::{
     async_trait,
     client::{Context, EventHandler},
-    model::{channel::{Reaction}, gateway::{Ready, GatewayIntents}, id::{UserId, ChannelId, MessageId}, guild::Member},
+    model::{channel::{Message, Reaction}, event::MessageUpdateEvent, gateway::{Ready, GatewayIntents}, id::{UserId, ChannelId, GuildId, MessageId}, guild::Member},
     http::Http,
     prelude::TypeMapKey
 };
@@ -37,10 +37,20 @@ impl TypeMapKey for Lavalink {
     type Value = LavalinkClient;
 }
 
-struct LavalinkHandler;
+struct LavalinkHandler {
+    loops: <MusicLoops as TypeMapKey>::Value
+}
 
 #[async_trait]
-impl LavalinkEventHandler for LavalinkHandler { }
+impl LavalinkEventHandler for LavalinkHandler {
+    async fn track_start(&self, client: LavalinkClient, event: TrackStart) {
+        commands::music::music_handler::track_start(&self.loops, client, event).await;
+    }
+
+    async fn track_finish(&self, client: LavalinkClient, event: TrackFinish) {
+        commands::music::music_handler::track_finish(&self.loops, client, event).await;
+    }
+}
 
 #[async_trait]
 impl EventHandler for Handler {
@@ -60,6 +70,18 @@ impl EventHandler for Handler {
         commands::cowboard::cowboard_handler::reaction_remove_all(&ctx, channel_id, removed_from_message_id).await;
     }
 
+    async fn message_update(&self, ctx: Context, _old: Option<Message>, _new: Option<Message>, event: MessageUpdateEvent) {
+        commands::cowboard::cowboard_handler::message_update(&ctx, event.channel_id, event.id, event.guild_id).await;
+    }
+
+    async fn message_delete(&self, ctx: Context, channel_id: ChannelId, deleted_message_id: MessageId, guild_id: Option<GuildId>) {
+        commands::cowboard::cowboard_handler::message_delete(&ctx, channel_id, &[deleted_message_id], guild_id).await;
+    }
+
+    async fn message_delete_bulk(&self, ctx: Context, channel_id: ChannelId, multiple_deleted_messages_ids: Vec<MessageId>, guild_id: Option<GuildId>) {
+        commands::cowboard::cowboard_handler::message_delete(&ctx, channel_id, &multiple_deleted_messages_ids, guild_id).await;
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         bot_init::ready(&ctx, &ready).await;
     }
@@ -166,6 +188,7 @@ async fn main() -> Result<(), Box<dyn error::Error>>  {
         let serenity = poise.client();
 
         let lavalink_enabled = !config.lavalink_ip.is_empty() && !config.lavalink_password.is_empty();
+        let music_loops: <MusicLoops as TypeMapKey>::Value = Arc::new(Mutex::new(HashMap::new()));
 
         if lavalink_enabled {
             match LavalinkClient::builder(*app_id.as_u64())
@@ -173,7 +196,7 @@ async fn main() -> Result<(), Box<dyn error::Error>>  {
                 .set_password(
                     config.lavalink_password,
                 )
-                .build(LavalinkHandler)
+                .build(LavalinkHandler { loops: music_loops.clone() })
                 .await {
                 Ok(lava_client) => {
                     let mut data = serenity.data.write().await;
@@ -188,11 +211,24 @@ async fn main() -> Result<(), Box<dyn error::Error>>  {
         {
             let mut data = serenity.data.write().await;
             data.insert::<Database>(database.clone());
+            data.insert::<SpamTracker>(Arc::new(Mutex::new(HashMap::new())));
+            data.insert::<SpamFilterCache>(Arc::new(Mutex::new(HashMap::new())));
+            data.insert::<CowboardQueue>(Arc::new(Mutex::new(HashMap::new())));
+            data.insert::<MusicLoops>(music_loops);
+            data.insert::<MusicFilters>(Arc::new(Mutex::new(HashMap::new())));
         }
 
-        // Start our reminder task and forget about it. Tokio allows us to start without await.
+        // Start our background tasks and forget about them. Tokio allows us to start without await.
         #[allow(clippy::let_underscore_future)]
         let _ = tokio::task::spawn(commands::ucm::reminders::check_reminders(serenity.data.clone(), serenity.cache_and_http.clone()));
+        #[allow(clippy::let_underscore_future)]
+        let _ = tokio::task::spawn(commands::season::check_seasons(serenity.data.clone(), serenity.cache_and_http.clone()));
+        #[allow(clippy::let_underscore_future)]
+        let _ = tokio::task::spawn(commands::general::rank_history::record_history(serenity.data.clone()));
+        #[allow(clippy::let_underscore_future)]
+        let _ = tokio::task::spawn(commands::rank_config::autofix::check_autofixes(serenity.data.clone(), serenity.cache_and_http.clone()));
+        #[allow(clippy::let_underscore_future)]
+        let _ = tokio::task::spawn(commands::cowboard::cowboard_digest::check_digests(serenity.data.clone(), serenity.cache_and_http.clone()));
 
         let commands = &poise.options().commands;
         let command_builders = poise::builtins::create_application_commands(commands);
@@ -1,3 +1,7 @@
+use std::fmt::{Display, Formatter};
+use chrono::{NaiveDate, NaiveDateTime};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
 use serenity::model::id::{RoleId, UserId};
 
 pub struct LevelUp {
@@ -28,11 +32,34 @@ impl Experience {
             xp: 0
         }
     }
+
+    // Uses a curve from Database::xp_curve that covers this level.
+    pub fn total(&self, curve: &[i64]) -> i64 {
+        curve.get(self.level.max(0) as usize).copied().unwrap_or(0) + self.xp as i64
+    }
+}
+
+// Add moves the member's level and xp by the given amounts, Set replaces them outright.
+pub enum ExperienceChange {
+    Add(Experience),
+    Set(Experience)
 }
 
 pub struct Disablements {
     pub channel: bool,
-    pub guild: bool
+    pub guild: bool,
+    pub overrides: Vec<TimeoutOverride>
+}
+
+pub struct Exclusions {
+    pub users: Vec<UserId>,
+    pub roles: Vec<RoleId>
+}
+
+impl Exclusions {
+    pub fn excludes(&self, user_id: UserId, roles: &[RoleId]) -> bool {
+        self.users.contains(&user_id) || roles.iter().any(|r| self.roles.contains(r))
+    }
 }
 
 pub struct Member {
@@ -52,8 +79,274 @@ pub struct Rank {
     pub min_level: i32
 }
 
+pub struct ExperienceSnapshot {
+    pub date: NaiveDate,
+    pub exp: Experience
+}
+
+pub struct ExperienceGain {
+    pub id: UserId,
+    pub before: Experience,
+    pub after: Experience
+}
+
 pub struct MemberPagination {
     pub members: Vec<Member>,
     pub current_page: i32,
     pub last_page: i32
-}
\ No newline at end of file
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum AnnounceMode {
+    Channel = 0,
+    Dedicated = 1,
+    Direct = 2,
+    Disabled = 3
+}
+
+impl Display for AnnounceMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnnounceMode::Channel => write!(f, "Same channel"),
+            AnnounceMode::Dedicated => write!(f, "Dedicated channel"),
+            AnnounceMode::Direct => write!(f, "Direct message"),
+            AnnounceMode::Disabled => write!(f, "Disabled")
+        }
+    }
+}
+
+impl TryFrom<u8> for AnnounceMode {
+    type Error = ();
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        FromPrimitive::from_u8(v).ok_or(())
+    }
+}
+
+impl TryFrom<&str> for AnnounceMode {
+    type Error = ();
+    fn try_from(v: &str) -> Result<Self, Self::Error> {
+        match v.to_lowercase().as_str() {
+            "channel" | "same" => Ok(AnnounceMode::Channel),
+            "dedicated" | "log" => Ok(AnnounceMode::Dedicated),
+            "dm" | "direct" => Ok(AnnounceMode::Direct),
+            "none" | "off" | "disabled" => Ok(AnnounceMode::Disabled),
+            &_ => Err(())
+        }
+    }
+}
+
+pub struct LevelUpConfig {
+    pub id: u64,
+    pub mode: AnnounceMode,
+    pub channel: Option<u64>,
+    pub template: Option<String>,
+    pub milestone_interval: i32,
+    pub rank_changes_only: bool
+}
+
+impl LevelUpConfig {
+    pub const DEFAULT_TEMPLATE: &'static str = "{user} leveled up from {old_level} to {new_level}.";
+
+    pub fn new(id: u64) -> Self {
+        LevelUpConfig {
+            id,
+            mode: AnnounceMode::Channel,
+            channel: None,
+            template: None,
+            milestone_interval: 1,
+            rank_changes_only: false
+        }
+    }
+
+    // Rank changes are always worth announcing; otherwise, only every n-th level is.
+    pub fn should_announce(&self, level: i32, rank_changed: bool) -> bool {
+        if self.mode == AnnounceMode::Disabled {
+            return false;
+        }
+
+        rank_changed || (!self.rank_changes_only && level % self.milestone_interval.max(1) == 0)
+    }
+
+    pub fn render(&self, user: UserId, old_level: i32, new_level: i32, new_rank: Option<RoleId>) -> String {
+        let role = new_rank.map(|r| format!("<@&{r}>")).unwrap_or_default();
+        let mut content = self.template.as_deref().unwrap_or(Self::DEFAULT_TEMPLATE)
+            .replace("{user}", &format!("<@{user}>"))
+            .replace("{old_level}", &old_level.to_string())
+            .replace("{new_level}", &new_level.to_string())
+            .replace("{new_role}", &role);
+
+        // The default message has always mentioned the new rank on its own line.
+        if self.template.is_none() && !role.is_empty() {
+            content += &format!("\nYou are now a {role}.");
+        }
+
+        content
+    }
+}
+
+pub struct SeasonConfig {
+    pub id: u64,
+    pub keep_percent: i32,
+    pub interval_days: Option<i32>,
+    pub next_close: Option<NaiveDateTime>,
+    pub champion_role: Option<u64>
+}
+
+impl SeasonConfig {
+    pub fn new(id: u64) -> Self {
+        SeasonConfig {
+            id,
+            keep_percent: 0,
+            interval_days: None,
+            next_close: None,
+            champion_role: None
+        }
+    }
+}
+
+pub struct ClosedSeason {
+    pub season: i32,
+    pub champion: Option<UserId>,
+    pub previous_champion: Option<UserId>
+}
+
+pub struct SeasonStanding {
+    pub exp: Experience,
+    pub rank: i64
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum SpamReason {
+    TooShort = 0,
+    Duplicate = 1,
+    EmojiOnly = 2,
+    StickerOnly = 3,
+    Burst = 4
+}
+
+impl Display for SpamReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpamReason::TooShort => write!(f, "Too short"),
+            SpamReason::Duplicate => write!(f, "Duplicate"),
+            SpamReason::EmojiOnly => write!(f, "Emoji only"),
+            SpamReason::StickerOnly => write!(f, "Sticker only"),
+            SpamReason::Burst => write!(f, "Burst")
+        }
+    }
+}
+
+impl TryFrom<u8> for SpamReason {
+    type Error = ();
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        FromPrimitive::from_u8(v).ok_or(())
+    }
+}
+
+#[derive(Clone)]
+pub struct SpamFilterConfig {
+    pub id: u64,
+    pub enabled: bool,
+    pub min_length: i32,
+    pub duplicate_threshold: i32,
+    pub block_emoji_only: bool,
+    pub block_sticker_only: bool,
+    pub burst_limit: i32,
+    pub burst_window: i32
+}
+
+impl SpamFilterConfig {
+    pub fn new(id: u64) -> Self {
+        SpamFilterConfig {
+            id,
+            enabled: false,
+            min_length: 3,
+            duplicate_threshold: 90,
+            block_emoji_only: true,
+            block_sticker_only: true,
+            burst_limit: 5,
+            burst_window: 10000
+        }
+    }
+}
+
+pub struct SpamCounters {
+    pub reasons: Vec<(SpamReason, i32)>,
+    pub offenders: Vec<(UserId, i32)>
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum TimeoutScope {
+    Channel = 0,
+    Role = 1
+}
+
+impl TryFrom<u8> for TimeoutScope {
+    type Error = ();
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        FromPrimitive::from_u8(v).ok_or(())
+    }
+}
+
+pub struct TimeoutOverride {
+    pub id: u64,
+    pub scope: TimeoutScope,
+    pub timeout: i32
+}
+
+impl TimeoutOverride {
+    // Role overrides follow the member and win over the channel's; when a member has several, the shortest applies.
+    pub fn resolve(overrides: &[TimeoutOverride], channel_id: u64, role_ids: &[u64]) -> Option<i32> {
+        let role = overrides.iter()
+            .filter(|o| o.scope == TimeoutScope::Role && role_ids.contains(&o.id))
+            .map(|o| o.timeout)
+            .min();
+
+        role.or_else(|| overrides.iter()
+            .find(|o| o.scope == TimeoutScope::Channel && o.id == channel_id)
+            .map(|o| o.timeout))
+    }
+}
+
+pub struct AutofixConfig {
+    pub id: u64,
+    pub enabled: bool,
+    pub dry_run: bool,
+    pub fix_multiple: bool,
+    pub fix_remove: bool,
+    pub fix_demote: bool,
+    pub log_channel: Option<u64>,
+    pub last_run: Option<NaiveDateTime>
+}
+
+impl AutofixConfig {
+    pub fn new(id: u64) -> Self {
+        AutofixConfig {
+            id,
+            enabled: false,
+            dry_run: true,
+            fix_multiple: false,
+            fix_remove: false,
+            fix_demote: false,
+            log_channel: None,
+            last_run: None
+        }
+    }
+}
+
+pub struct MusicConfig {
+    pub id: u64,
+    pub default_volume: i32
+}
+
+impl MusicConfig {
+    pub fn new(id: u64) -> Self {
+        MusicConfig {
+            id,
+            default_volume: 100
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use serenity::model::id::GuildId;
+use rust_decimal::{
+    Decimal,
+    prelude::FromPrimitive
+};
+use rust_decimal::prelude::ToPrimitive;
+use tiberius::Row;
+
+use crate::Database;
+use crate::models::db_models::*;
+
+fn to_config(id: u64, row: &Row) -> AutofixConfig {
+    let log_channel: Option<Decimal> = row.get(5);
+    AutofixConfig {
+        id,
+        enabled: row.get(0).unwrap(),
+        dry_run: row.get(1).unwrap(),
+        fix_multiple: row.get(2).unwrap(),
+        fix_remove: row.get(3).unwrap(),
+        fix_demote: row.get(4).unwrap(),
+        log_channel: log_channel.and_then(|o| o.to_u64()),
+        last_run: row.get(6)
+    }
+}
+
+impl Database {
+    pub async fn get_autofix_config(&self, server_id: GuildId) -> Result<AutofixConfig, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT enabled, dry_run, fix_multiple, fix_remove, fix_demote, log_channel, last_run FROM [Ranking].[AutofixConfig] WHERE id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        Ok(res.map(|row| to_config(server_id.0, &row)).unwrap_or_else(|| AutofixConfig::new(server_id.0)))
+    }
+
+    pub async fn update_autofix_config(&self, config: &AutofixConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(config.id).unwrap();
+        let log_channel = config.log_channel.map(|o| Decimal::from_u64(o).unwrap());
+
+        conn.execute(
+            "UPDATE [Ranking].[AutofixConfig] SET enabled = @P2, dry_run = @P3, fix_multiple = @P4, fix_remove = @P5, fix_demote = @P6, log_channel = @P7, last_run = @P8 WHERE id = @P1; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Ranking].[AutofixConfig] (id, enabled, dry_run, fix_multiple, fix_remove, fix_demote, log_channel, last_run) VALUES (@P1, @P2, @P3, @P4, @P5, @P6, @P7, @P8);",
+            &[&server, &config.enabled, &config.dry_run, &config.fix_multiple, &config.fix_remove, &config.fix_demote, &log_channel, &config.last_run])
+            .await?;
+
+        Ok(())
+    }
+
+    // Servers that haven't had their nightly run yet, once it's past the given hour (UTC).
+    pub async fn get_due_autofixes(&self, hour: i32) -> Result<Vec<AutofixConfig>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let res = conn.query(
+            "SELECT enabled, dry_run, fix_multiple, fix_remove, fix_demote, log_channel, last_run, id FROM [Ranking].[AutofixConfig] \
+            WHERE enabled = 1 AND DATEPART(HOUR, SYSUTCDATETIME()) >= @P1 \
+            AND (last_run IS NULL OR CAST(last_run AS DATE) < CAST(SYSUTCDATETIME() AS DATE))",
+            &[&hour])
+            .await?
+            .into_first_result()
+            .await?
+            .into_iter()
+            .map(|row| {
+                let id: Decimal = row.get(7).unwrap();
+                to_config(id.to_u64().unwrap(), &row)
+            })
+            .collect();
+
+        Ok(res)
+    }
+}
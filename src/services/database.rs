@@ -9,7 +9,7 @@ use serenity::{
     },
     prelude::TypeMapKey
 };
-use tiberius::{AuthMethod, Config};
+use tiberius::{AuthMethod, Config, Row};
 use rust_decimal::{
     Decimal,
     prelude::FromPrimitive
@@ -25,6 +25,22 @@ impl TypeMapKey for Database {
     type Value = Arc<Database>;
 }
 
+// Hidden members are passed to SQL Server as a comma-separated list for STRING_SPLIT.
+fn join_ids(ids: &[UserId]) -> String {
+    ids.iter().map(|id| id.0.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn to_timeout_override(row: &Row) -> Option<TimeoutOverride> {
+    let scope: u8 = row.get(0).unwrap();
+    let id: u64 = row.get(1).and_then(|u: rust_decimal::Decimal| u.to_u64()).unwrap();
+
+    TimeoutScope::try_from(scope).ok().map(|scope| TimeoutOverride {
+        id,
+        scope,
+        timeout: row.get(2).unwrap()
+    })
+}
+
 impl Database {
     pub async fn new(ip: &str, port: u16, usr: &str, pwd: &str) -> Result<Self, bb8_tiberius::Error> {
         // The password is stored in a file; using secure strings is probably not going to make much of a difference.
@@ -43,13 +59,14 @@ impl Database {
         Ok(Database { pool })
     }
 
-    pub async fn provide_exp(&self, server_id: GuildId, user_id: UserId) -> Result<LevelUp, Box<dyn std::error::Error + Send + Sync>> {
+    // A missing timeout falls back to the server-wide cooldown.
+    pub async fn provide_exp(&self, server_id: GuildId, user_id: UserId, timeout: Option<i32>) -> Result<LevelUp, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
         let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
         let res = conn.query(
-            "EXEC Ranking.ProvideExp @serverid = @P1, @userid = @P2",
-            &[&server, &user])
+            "EXEC Ranking.ProvideExp @serverid = @P1, @userid = @P2, @timeout = @P3",
+            &[&server, &user, &timeout])
             .await?
             .into_row()
             .await?;
@@ -79,7 +96,7 @@ impl Database {
         Ok(out)
     }
 
-    pub async fn get_xp(&self, server_id: GuildId, user_id: UserId) -> Result<Experience, Box<dyn std::error::Error>> {
+    pub async fn get_xp(&self, server_id: GuildId, user_id: UserId) -> Result<Experience, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
         let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
@@ -102,7 +119,7 @@ impl Database {
         Ok(out)
     }
 
-    pub async fn get_highest_role(&self, server_id: GuildId, level: i32) -> Result<Option<RoleId>, Box<dyn std::error::Error>> {
+    pub async fn get_highest_role(&self, server_id: GuildId, level: i32) -> Result<Option<RoleId>, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
         let res = conn.query(
@@ -122,7 +139,7 @@ impl Database {
         Ok(out)
     }
 
-    pub async fn calculate_level(&self, level: i32) -> Result<i32, Box<dyn std::error::Error>> {
+    pub async fn calculate_level(&self, level: i32) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let res = conn.query(
             "EXEC [Ranking].[CalculateLevel] @level = @P1",
@@ -183,6 +200,91 @@ impl Database {
         Ok(out)
     }
 
+    pub async fn get_exclusions(&self, server_id: GuildId) -> Result<Exclusions, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT target_id, is_role FROM [Ranking].[Exclusion] WHERE server_id = @P1",
+            &[&server])
+            .await?
+            .into_first_result()
+            .await?;
+
+        let mut out = Exclusions {
+            users: Vec::new(),
+            roles: Vec::new()
+        };
+
+        for row in res {
+            let id = row.get(0).and_then(|u: rust_decimal::Decimal| u.to_u64()).unwrap();
+            let is_role: bool = row.get(1).unwrap();
+
+            if is_role {
+                out.roles.push(RoleId::from(id));
+            } else {
+                out.users.push(UserId::from(id));
+            }
+        }
+
+        Ok(out)
+    }
+
+    // True: excluded False: included
+    pub async fn toggle_exclusion(&self, server_id: GuildId, target_id: u64, is_role: bool) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let target = Decimal::from_u64(target_id).unwrap();
+        let removed = conn.execute(
+            "DELETE FROM [Ranking].[Exclusion] WHERE server_id = @P1 AND target_id = @P2 AND is_role = @P3",
+            &[&server, &target, &is_role])
+            .await?
+            .total();
+
+        if removed > 0 {
+            return Ok(false);
+        }
+
+        conn.execute(
+            "INSERT INTO [Ranking].[Exclusion] (server_id, target_id, is_role) VALUES (@P1, @P2, @P3)",
+            &[&server, &target, &is_role])
+            .await?;
+
+        Ok(true)
+    }
+
+    // True: members keep every rank role they earn. False: only their highest rank.
+    pub async fn get_role_stacking(&self, server_id: GuildId) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT stack_roles FROM [Ranking].[Server] WHERE id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        let mut out: bool = false;
+
+        if let Some(item) = res {
+            let stack_roles: Option<bool> = item.get(0);
+            out = stack_roles.unwrap_or_default();
+        }
+
+        Ok(out)
+    }
+
+    pub async fn set_role_stacking(&self, server_id: GuildId, stack: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        conn.execute(
+            "UPDATE [Ranking].[Server] SET stack_roles = @P2 WHERE id = @P1",
+            &[&server, &stack])
+            .await?;
+
+        Ok(())
+    }
+
+    // Also carries the cooldown overrides that could apply to a message here: every role override, and this channel's.
     pub async fn get_disablements(&self, server_id: GuildId, channel_id: ChannelId) -> Result<Disablements, Box<dyn std::error::Error>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
@@ -191,40 +293,51 @@ impl Database {
         let res = conn.query(
             "SELECT \
                 (SELECT CAST(1 AS BIT) FROM [Ranking].[DisabledChannel] WHERE server_id = @P1 AND channel_id = @P2), \
-                (SELECT ranking_disabled FROM [Ranking].[Server] WHERE id = @P1);",
+                (SELECT ranking_disabled FROM [Ranking].[Server] WHERE id = @P1); \
+            SELECT scope, target_id, timeout FROM [Ranking].[TimeoutOverride] WHERE server_id = @P1 AND (scope = 1 OR target_id = @P2);",
             &[&server, &channel])
             .await?
-            .into_row()
+            .into_results()
             .await?;
 
         let mut out: Disablements = Disablements {
             channel: false,
-            guild: false
+            guild: false,
+            overrides: res.get(1).map(|rows| rows.iter().filter_map(to_timeout_override).collect()).unwrap_or_default()
         };
 
-        if let Some(item) = res {
+        if let Some(item) = res.first().and_then(|rows| rows.first()) {
             let channel_disabled: Option<bool> = item.get(0);
             let guild_disabled: Option<bool> = item.get(1);
 
-            out = Disablements {
-                channel: channel_disabled.unwrap_or_default(),
-                guild: guild_disabled.unwrap_or_default()
-            };
+            out.channel = channel_disabled.unwrap_or_default();
+            out.guild = guild_disabled.unwrap_or_default();
         }
 
         Ok(out)
     }
 
     // Page number is zero-indexed.
-    pub async fn top_members(&self, server_id: GuildId, page: i32) -> Result<MemberPagination, Box<dyn std::error::Error + Send + Sync>> {
+    // Hidden members are left out of the rankings, on top of those excluded by user.
+    pub async fn top_members(&self, server_id: GuildId, page: i32, hidden: &[UserId]) -> Result<MemberPagination, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
         const ROWS_FETCHED: i32 = 10;
         let mut offset = page * ROWS_FETCHED;
         offset = offset.max(0);
+        let hidden = join_ids(hidden);
         let res = conn.query(
-            "SELECT user_id, level, xp FROM [Ranking].[Level] WHERE server_id = @P1 ORDER BY level DESC, xp DESC OFFSET @P2 ROWS FETCH NEXT @P3 ROWS ONLY; SELECT COUNT(1) FROM [Ranking].[Level] WHERE server_id = @P1",
-            &[&server, &offset, &ROWS_FETCHED])
+            r#"
+            DECLARE @visible TABLE (user_id DECIMAL(20, 0), level INT, xp INT);
+            INSERT INTO @visible
+            SELECT user_id, level, xp FROM [Ranking].[Level] l
+            WHERE server_id = @P1
+                AND NOT EXISTS (SELECT 1 FROM [Ranking].[Exclusion] e WHERE e.server_id = l.server_id AND e.target_id = l.user_id AND e.is_role = 0)
+                AND NOT EXISTS (SELECT 1 FROM STRING_SPLIT(@P4, ',') h WHERE h.value = CAST(l.user_id AS VARCHAR(20)));
+            SELECT user_id, level, xp FROM @visible ORDER BY level DESC, xp DESC OFFSET @P2 ROWS FETCH NEXT @P3 ROWS ONLY;
+            SELECT COUNT(1) FROM @visible;
+            "#,
+            &[&server, &offset, &ROWS_FETCHED, &hidden])
             .await?
             .into_results()
             .await?;
@@ -253,13 +366,16 @@ impl Database {
         })
     }
 
-    pub async fn rank_within_members(&self, server_id: GuildId, user_id: UserId) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    pub async fn rank_within_members(&self, server_id: GuildId, user_id: UserId, hidden: &[UserId]) -> Result<Option<i64>, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
         let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
+        let hidden = join_ids(hidden);
         let res = conn.query(
-            "SELECT row_number FROM (SELECT user_id, ROW_NUMBER() OVER (ORDER BY level DESC, xp DESC) AS row_number FROM [Ranking].[Level] WHERE server_id = @P1) mukyu WHERE user_id = @P2",
-            &[&server, &user])
+            "SELECT row_number FROM (SELECT user_id, ROW_NUMBER() OVER (ORDER BY level DESC, xp DESC) AS row_number FROM [Ranking].[Level] l WHERE server_id = @P1 \
+                AND NOT EXISTS (SELECT 1 FROM [Ranking].[Exclusion] e WHERE e.server_id = l.server_id AND e.target_id = l.user_id AND e.is_role = 0) \
+                AND NOT EXISTS (SELECT 1 FROM STRING_SPLIT(@P3, ',') h WHERE h.value = CAST(l.user_id AS VARCHAR(20)))) mukyu WHERE user_id = @P2",
+            &[&server, &user, &hidden])
             .await?
             .into_row()
             .await?;
@@ -383,6 +499,49 @@ impl Database {
         Ok(out)
     }
 
+    pub async fn set_timeout_override(&self, server_id: GuildId, scope: TimeoutScope, id: u64, timeout: Option<i32>) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let id = Decimal::from_u64(id).unwrap();
+        let scope = scope as u8;
+
+        let affected = if let Some(timeout) = timeout {
+            conn.execute(
+                r#"
+                UPDATE [Ranking].[TimeoutOverride] SET timeout = @P4 WHERE server_id = @P1 AND scope = @P2 AND target_id = @P3;
+                IF @@ROWCOUNT = 0
+                    INSERT INTO [Ranking].[TimeoutOverride] (server_id, scope, target_id, timeout) VALUES (@P1, @P2, @P3, @P4);
+                "#,
+                &[&server, &scope, &id, &timeout])
+                .await?
+                .total()
+        } else {
+            conn.execute(
+                "DELETE FROM [Ranking].[TimeoutOverride] WHERE server_id = @P1 AND scope = @P2 AND target_id = @P3",
+                &[&server, &scope, &id])
+                .await?
+                .total()
+        };
+
+        Ok(affected > 0)
+    }
+
+    pub async fn get_timeout_overrides(&self, server_id: GuildId) -> Result<Vec<TimeoutOverride>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT scope, target_id, timeout FROM [Ranking].[TimeoutOverride] WHERE server_id = @P1 ORDER BY scope, timeout",
+            &[&server])
+            .await?
+            .into_first_result()
+            .await?
+            .into_iter()
+            .filter_map(|row| to_timeout_override(&row))
+            .collect();
+
+        Ok(res)
+    }
+
     pub async fn get_users(&self, server_id: GuildId) -> Result<Vec<FullMember>, Box<dyn std::error::Error + Send + Sync>> {
         let mut conn = self.pool.get().await?;
         let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
@@ -409,4 +568,99 @@ impl Database {
 
         Ok(res)
     }
+
+    // Applies the change and carries excess (or negative) xp over into levels, using the same curve as ProvideExp.
+    // The row stays locked until the end, so chat experience and other changes can't slip in between; returns the before and after.
+    pub async fn change_xp(&self, server_id: GuildId, user_id: UserId, change: ExperienceChange) -> Result<(Experience, Experience), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
+        let (relative, level, xp) = match change {
+            ExperienceChange::Add(o) => (true, o.level, o.xp),
+            ExperienceChange::Set(o) => (false, o.level, o.xp)
+        };
+
+        let res = conn.query(
+            r#"
+            SET XACT_ABORT ON;
+            BEGIN TRANSACTION;
+            DECLARE @old_level INT = 0, @old_xp INT = 0;
+            SELECT @old_level = level, @old_xp = xp FROM [Ranking].[Level] WITH (UPDLOCK, HOLDLOCK) WHERE server_id = @P1 AND [user_id] = @P2;
+
+            DECLARE @level BIGINT = @P4, @xp BIGINT = @P5;
+            IF @P3 = 1
+                SELECT @level = @level + @old_level, @xp = @xp + @old_xp;
+            IF @level < 0
+                SET @level = 0;
+
+            DECLARE @step TABLE (xp INT);
+            DECLARE @next INT;
+            WHILE 1 = 1
+            BEGIN
+                DELETE FROM @step;
+                INSERT INTO @step EXEC [Ranking].[CalculateLevel] @level = @level;
+                SET @next = (SELECT TOP 1 xp FROM @step);
+                IF @next IS NULL OR @next <= 0 OR @xp < @next
+                    BREAK;
+                SELECT @xp = @xp - @next, @level = @level + 1;
+            END
+            WHILE @xp < 0 AND @level > 0
+            BEGIN
+                SET @level = @level - 1;
+                DELETE FROM @step;
+                INSERT INTO @step EXEC [Ranking].[CalculateLevel] @level = @level;
+                SET @xp = @xp + ISNULL((SELECT TOP 1 xp FROM @step), 0);
+            END
+
+            DECLARE @new_level INT = IIF(@level > 2147483647, 2147483647, @level);
+            DECLARE @new_xp INT = IIF(@xp < 0, 0, IIF(@xp > 2147483647, 2147483647, @xp));
+            UPDATE [Ranking].[Level] SET xp = @new_xp, level = @new_level WHERE server_id = @P1 AND [user_id] = @P2;
+            IF @@ROWCOUNT = 0
+                INSERT INTO [Ranking].[Level] (server_id, [user_id], xp, level) VALUES (@P1, @P2, @new_xp, @new_level);
+            COMMIT TRANSACTION;
+
+            SELECT @old_level, @old_xp, @new_level, @new_xp;
+            "#,
+            &[&server, &user, &relative, &level, &xp])
+            .await?
+            .into_row()
+            .await?
+            .ok_or("Changing experience returned nothing")?;
+
+        Ok((
+            Experience {
+                level: res.get(0).unwrap(),
+                xp: res.get(1).unwrap()
+            },
+            Experience {
+                level: res.get(2).unwrap(),
+                xp: res.get(3).unwrap()
+            }
+        ))
+    }
+
+    pub async fn reset_xp(&self, server_id: GuildId, user_id: UserId) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
+        let total = conn.execute(
+            "DELETE FROM [Ranking].[Level] WHERE server_id = @P1 AND [user_id] = @P2",
+            &[&server, &user])
+            .await?
+            .total();
+
+        Ok(total > 0)
+    }
+
+    pub async fn reset_server_xp(&self, server_id: GuildId) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let total = conn.execute(
+            "DELETE FROM [Ranking].[Level] WHERE server_id = @P1",
+            &[&server])
+            .await?
+            .total();
+
+        Ok(total)
+    }
 }
\ No newline at end of file
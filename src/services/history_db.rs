@@ -0,0 +1,152 @@
+use chrono::NaiveDate;
+use serenity::model::id::{GuildId, UserId};
+use rust_decimal::{
+    Decimal,
+    prelude::FromPrimitive
+};
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::Database;
+use crate::models::db_models::*;
+
+impl Database {
+    // Keeps one row per member per day; today's row is overwritten until the day is over.
+    pub async fn snapshot_xp(&self) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let total = conn.execute(
+            r#"
+            DECLARE @today DATE = CAST(GETUTCDATE() AS DATE);
+
+            UPDATE h SET h.level = l.level, h.xp = l.xp
+            FROM [Ranking].[LevelHistory] h
+            INNER JOIN [Ranking].[Level] l ON l.server_id = h.server_id AND l.[user_id] = h.[user_id]
+            WHERE h.snapshot_date = @today;
+
+            INSERT INTO [Ranking].[LevelHistory] (server_id, [user_id], snapshot_date, level, xp)
+            SELECT l.server_id, l.[user_id], @today, l.level, l.xp
+            FROM [Ranking].[Level] l
+            WHERE NOT EXISTS (
+                SELECT 1 FROM [Ranking].[LevelHistory] h
+                WHERE h.server_id = l.server_id AND h.[user_id] = l.[user_id] AND h.snapshot_date = @today
+            );
+            "#,
+            &[])
+            .await?
+            .total();
+
+        Ok(total)
+    }
+
+    pub async fn get_xp_history(&self, server_id: GuildId, user_id: UserId, days: i32) -> Result<Vec<ExperienceSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT snapshot_date, level, xp FROM [Ranking].[LevelHistory] \
+            WHERE server_id = @P1 AND [user_id] = @P2 AND snapshot_date >= DATEADD(DAY, -@P3, CAST(GETUTCDATE() AS DATE)) \
+            ORDER BY snapshot_date ASC",
+            &[&server, &user, &days])
+            .await?
+            .into_first_result()
+            .await?
+            .into_iter()
+            .map(|row| {
+                let date: NaiveDate = row.get(0).unwrap();
+                ExperienceSnapshot {
+                    date,
+                    exp: Experience {
+                        level: row.get(1).unwrap(),
+                        xp: row.get(2).unwrap()
+                    }
+                }
+            })
+            .collect();
+
+        Ok(res)
+    }
+
+    // Compares everyone's current experience to their snapshot from `days` ago.
+    // Members who started being tracked later are compared to their earliest snapshot instead.
+    pub async fn get_xp_gains(&self, server_id: GuildId, days: i32) -> Result<Vec<ExperienceGain>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            r#"
+            DECLARE @cutoff DATE = DATEADD(DAY, -@P2, CAST(GETUTCDATE() AS DATE));
+
+            SELECT l.[user_id], COALESCE(b.level, a.level), COALESCE(b.xp, a.xp), l.level, l.xp
+            FROM [Ranking].[Level] l
+            OUTER APPLY (
+                SELECT TOP 1 h.level, h.xp FROM [Ranking].[LevelHistory] h
+                WHERE h.server_id = l.server_id AND h.[user_id] = l.[user_id] AND h.snapshot_date <= @cutoff
+                ORDER BY h.snapshot_date DESC
+            ) b
+            OUTER APPLY (
+                SELECT TOP 1 h.level, h.xp FROM [Ranking].[LevelHistory] h
+                WHERE h.server_id = l.server_id AND h.[user_id] = l.[user_id] AND h.snapshot_date > @cutoff
+                ORDER BY h.snapshot_date ASC
+            ) a
+            WHERE l.server_id = @P1 AND (b.level IS NOT NULL OR a.level IS NOT NULL)
+            "#,
+            &[&server, &days])
+            .await?
+            .into_first_result()
+            .await?
+            .into_iter()
+            .map(|row| {
+                let id: UserId = row.get(0).and_then(|u: rust_decimal::Decimal| u.to_u64()).map(UserId::from).unwrap();
+                ExperienceGain {
+                    id,
+                    before: Experience {
+                        level: row.get(1).unwrap(),
+                        xp: row.get(2).unwrap()
+                    },
+                    after: Experience {
+                        level: row.get(3).unwrap(),
+                        xp: row.get(4).unwrap()
+                    }
+                }
+            })
+            .collect();
+
+        Ok(res)
+    }
+
+    // The total xp required to reach each level up to max_level, so experience from different levels can be compared.
+    pub async fn xp_curve(&self, max_level: i32) -> Result<Vec<i64>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let max_level = max_level.max(0);
+        let res = conn.query(
+            r#"
+            DECLARE @curve TABLE (level INT, xp INT);
+            DECLARE @step TABLE (xp INT);
+            DECLARE @level INT = 0;
+
+            WHILE @level < @P1
+            BEGIN
+                DELETE FROM @step;
+                INSERT INTO @step EXEC [Ranking].[CalculateLevel] @level = @level;
+                INSERT INTO @curve SELECT @level, ISNULL((SELECT TOP 1 xp FROM @step), 0);
+                SET @level = @level + 1;
+            END
+
+            SELECT xp FROM @curve ORDER BY level;
+            "#,
+            &[&max_level])
+            .await?
+            .into_first_result()
+            .await?;
+
+        let mut curve = Vec::with_capacity(max_level as usize + 1);
+        let mut total: i64 = 0;
+        curve.push(total);
+
+        for row in res {
+            let xp: i32 = row.get(0).unwrap();
+            total += xp as i64;
+            curve.push(total);
+        }
+
+        Ok(curve)
+    }
+}
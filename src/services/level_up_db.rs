@@ -0,0 +1,55 @@
+use serenity::model::id::GuildId;
+use rust_decimal::{
+    Decimal,
+    prelude::FromPrimitive
+};
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::Database;
+use crate::models::db_models::*;
+
+impl Database {
+    pub async fn get_level_up_config(&self, server_id: GuildId) -> Result<LevelUpConfig, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT mode, channel, template, milestone_interval, rank_changes_only FROM [Ranking].[LevelUpConfig] WHERE id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        let mut out = LevelUpConfig::new(server_id.0);
+
+        if let Some(item) = res {
+            let mode: u8 = item.get(0).unwrap();
+            let channel_id: Option<Decimal> = item.get(1);
+            let template: Option<&str> = item.get(2);
+            out = LevelUpConfig {
+                id: server_id.0,
+                mode: AnnounceMode::try_from(mode).unwrap_or(AnnounceMode::Channel),
+                channel: channel_id.and_then(|o| o.to_u64()),
+                template: template.map(|o| o.to_string()),
+                milestone_interval: item.get(3).unwrap(),
+                rank_changes_only: item.get(4).unwrap()
+            };
+        }
+
+        Ok(out)
+    }
+
+    pub async fn update_level_up_config(&self, config: &LevelUpConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(config.id).unwrap();
+        let channel = config.channel.map(|o| Decimal::from_u64(o).unwrap());
+        let mode = config.mode as u8;
+
+        conn.execute(
+            "UPDATE [Ranking].[LevelUpConfig] SET mode = @P2, channel = @P3, template = @P4, milestone_interval = @P5, rank_changes_only = @P6 WHERE id = @P1; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Ranking].[LevelUpConfig] (id, mode, channel, template, milestone_interval, rank_changes_only) VALUES (@P1, @P2, @P3, @P4, @P5, @P6);",
+            &[&server, &mode, &channel, &config.template, &config.milestone_interval, &config.rank_changes_only])
+            .await?;
+
+        Ok(())
+    }
+}
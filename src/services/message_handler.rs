@@ -1,10 +1,13 @@
 use serenity::{
     client::Context,
-    model::{id::{RoleId}, guild::Member}
+    http::Http,
+    model::{id::{ChannelId, GuildId, RoleId, UserId}, guild::Member, user::User}
 };
 use tracing::error;
 use serenity::model::channel::Message;
 use crate::{Database, db, Error};
+use crate::services::spam_filter::{self, SpamFilterCache, SpamTracker};
+use crate::models::db_models::{AnnounceMode, Experience, ExperienceChange, LevelUp, LevelUpConfig, Rank, TimeoutOverride};
 use crate::models::minecraft_db_models::*;
 use proto_mc::rcon::RCONClient;
 use crate::models::minecraft_db_models::Message as MCMessage;
@@ -158,18 +161,40 @@ async fn ranking_check(ctx: &Context, msg: &Message) {
     let db = db!(ctx);
 
     if let Some(guild) = msg.guild(ctx) {
-        match db.get_disablements(guild.id, msg.channel_id).await {
+        let overrides = match db.get_disablements(guild.id, msg.channel_id).await {
             Err(ex) => {
                 error!("Failed checking if the current channel or guild was disabled: {}", ex);
+                Vec::new()
             },
             Ok(result) => {
                 if result.channel || result.guild {
                     return;
                 }
+
+                result.overrides
+            }
+        };
+
+        match db.get_exclusions(guild.id).await {
+            Err(ex) => {
+                error!("Failed checking if the author was excluded: {}", ex);
+            },
+            Ok(exclusions) => {
+                let roles = msg.member.as_ref().map(|m| m.roles.clone()).unwrap_or_default();
+                if exclusions.excludes(author.id, &roles) {
+                    return;
+                }
             }
         }
 
-        match db.provide_exp(guild.id, author.id).await {
+        if spam_check(ctx, guild.id, msg).await {
+            return;
+        }
+
+        let roles: Vec<u64> = msg.member.as_ref().map(|m| m.roles.iter().map(|r| r.0).collect()).unwrap_or_default();
+        let timeout = TimeoutOverride::resolve(&overrides, msg.channel_id.0, &roles);
+
+        match db.provide_exp(guild.id, author.id, timeout).await {
             Err(ex) => {
                 error!("Failed providing exp to user: {}", ex)
             },
@@ -178,49 +203,216 @@ async fn ranking_check(ctx: &Context, msg: &Message) {
                     return;
                 }
 
-                let mut content = format!("<@{}> leveled up from {} to {}.", author.id.as_u64(), data.level - 1, data.level);
-                if let Some(new_rank_id) = data.new_rank {
-                    content += &format!("\nYou are now a <@&{new_rank_id}>.");
-
-                    let mut error = false;
-
-                    match msg.member(&ctx.http).await {
-                        Ok(mut member) => {
-                            if let Some(old_rank_id) = data.old_rank {
-                                let old_rank = RoleId::from(old_rank_id);
-                                if member.roles.contains(&old_rank) {
-                                    // We know we're in a guild, so an error is probably an API issue.
-                                    if let Err(ex) = member.remove_role(&ctx.http, old_rank).await {
-                                        error = true;
-                                        content += "\n(We failed to update your roles; maybe we don't have permission?)";
-                                        error!("Failed to remove role from user: {}", ex);
-                                    }
-                                }
-                            }
-
-                            if let Err(ex) = member.add_role(&ctx.http, RoleId::from(new_rank_id)).await {
-                                if !error {
-                                    content += "\n(We failed to update your roles; maybe we don't have permission?)";
-                                }
-                                error!("Failed to add role to user: {}", ex);
-                            }
-                        }
-                        Err(ex) => {
-                            error!("Failed to get member from message: {}", ex);
-                        }
-                    }
-                }
+                level_up(ctx, guild.id, msg.channel_id, author, data.level - 1, &data).await;
+            }
+        }
+    }
+}
+
+// True if the server's spam filter says this message shouldn't earn experience.
+async fn spam_check(ctx: &Context, guild_id: GuildId, msg: &Message) -> bool {
+    let db = db!(ctx);
+
+    let (tracker, cache) = {
+        let ctx_global = ctx.data.read().await;
+        (ctx_global.get::<SpamTracker>().expect("Couldn't find spam tracker").clone(),
+            ctx_global.get::<SpamFilterCache>().expect("Couldn't find spam filter cache").clone())
+    };
+
+    let cached = cache.lock().unwrap().get(&guild_id).cloned();
+    let config = match cached {
+        Some(config) => config,
+        None => match db.get_spam_filter_config(guild_id).await {
+            Ok(config) => {
+                cache.lock().unwrap().insert(guild_id, config.clone());
+                config
+            }
+            Err(ex) => {
+                error!("Failed to get spam filter settings: {}", ex);
+                return false;
+            }
+        }
+    };
+
+    if !config.enabled {
+        return false;
+    }
+
+    if let Some(reason) = spam_filter::check_message(&tracker, &config, guild_id, msg) {
+        if let Err(ex) = db.add_spam_strike(guild_id, msg.author.id, reason).await {
+            error!("Failed to record spam strike: {}", ex);
+        }
+
+        return true;
+    }
+
+    false
+}
+
+// Adds (or with a negative amount, takes away) bonus experience outside of chatting.
+// Gains go through the usual level-up flow; losses just move the member's rank roles back down.
+pub async fn award_xp(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, user: &User, amount: i32) -> Result<(), Error> {
+    let db = db!(ctx);
+
+    let (old, new) = db.change_xp(guild_id, user.id, ExperienceChange::Add(Experience {
+        level: 0,
+        xp: amount
+    })).await?;
+
+    if new.level > old.level {
+        let old_rank = db.get_highest_role(guild_id, old.level).await?;
+        let new_rank = db.get_highest_role(guild_id, new.level).await?;
+        let changed = old_rank != new_rank;
+
+        let data = LevelUp {
+            level: new.level,
+            old_rank: old_rank.filter(|_| changed).map(|r| r.0),
+            new_rank: new_rank.filter(|_| changed).map(|r| r.0)
+        };
+
+        level_up(ctx, guild_id, channel_id, user, old.level, &data).await;
+    } else if new.level < old.level {
+        sync_rank_role(&db, &ctx.http, guild_id, user.id, old.level, new.level).await?;
+    }
+
+    Ok(())
+}
+
+// Promotes the member if their rank changed, then announces it according to the server's level-up settings.
+pub async fn level_up(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, user: &User, old_level: i32, data: &LevelUp) {
+    let db = db!(ctx);
 
-                if let Err(ex2) =
-                    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| e
-                        .title("Level Up!")
-                        .description(content)
-                    )).await {
-                    error!("Error sending level-up message: {}", ex2)
-                };
+    let config = match db.get_level_up_config(guild_id).await {
+        Ok(config) => config,
+        Err(ex) => {
+            error!("Failed to get level-up settings: {}", ex);
+            LevelUpConfig::new(guild_id.0)
+        }
+    };
+
+    let new_rank = data.new_rank.map(RoleId::from);
+    let mut role_error = false;
+
+    if new_rank.is_some() {
+        let stack = match db.get_role_stacking(guild_id).await {
+            Ok(stack) => stack,
+            Err(ex) => {
+                error!("Failed to get role stacking mode: {}", ex);
+                false
+            }
+        };
+
+        // When stacking, members get to keep their old rank.
+        let old_rank = if stack { None } else { data.old_rank.map(RoleId::from) };
+
+        match guild_id.member(ctx, user.id).await {
+            Ok(mut member) => {
+                role_error = !update_rank_role(&ctx.http, &mut member, old_rank, new_rank).await;
+            }
+            Err(ex) => {
+                error!("Failed to get member for level up: {}", ex);
+            }
+        }
+    }
+
+    if !config.should_announce(data.level, new_rank.is_some()) {
+        return;
+    }
+
+    let mut content = config.render(user.id, old_level, data.level, new_rank);
+    if role_error {
+        content += "\n(We failed to update your roles; maybe we don't have permission?)";
+    }
+
+    let target = match config.mode {
+        AnnounceMode::Dedicated => config.channel.map(ChannelId::from).unwrap_or(channel_id),
+        AnnounceMode::Direct => match user.create_dm_channel(ctx).await {
+            Ok(dm) => dm.id,
+            Err(ex) => {
+                error!("Failed to open DMs for level-up message: {}", ex);
+                return;
+            }
+        },
+        _ => channel_id
+    };
+
+    if let Err(ex) =
+        target.send_message(&ctx.http, |m| m.embed(|e| e
+            .title("Level Up!")
+            .description(content)
+        )).await {
+        error!("Error sending level-up message: {}", ex)
+    };
+}
+
+// Swaps a member's rank role from the old rank to the new one. Returns false if Discord refused either change.
+pub async fn update_rank_role(http: &Http, member: &mut Member, old_rank: Option<RoleId>, new_rank: Option<RoleId>) -> bool {
+    let mut success = true;
+
+    if let Some(old_rank) = old_rank {
+        if Some(old_rank) != new_rank && member.roles.contains(&old_rank) {
+            // We know we're in a guild, so an error is probably an API issue.
+            if let Err(ex) = member.remove_role(http, old_rank).await {
+                success = false;
+                error!("Failed to remove role from user: {}", ex);
+            }
+        }
+    }
+
+    if let Some(new_rank) = new_rank {
+        if !member.roles.contains(&new_rank) {
+            if let Err(ex) = member.add_role(http, new_rank).await {
+                success = false;
+                error!("Failed to add role to user: {}", ex);
+            }
+        }
+    }
+
+    success
+}
+
+// Recomputes a member's rank role after their level was changed outside of ranking_check.
+pub async fn sync_rank_role(db: &Database, http: &Http, guild_id: GuildId, user_id: UserId, old_level: i32, new_level: i32) -> Result<bool, Error> {
+    if db.get_role_stacking(guild_id).await? {
+        let ranks = db.get_roles(guild_id).await?;
+        let mut member = guild_id.member(http, user_id).await?;
+        return Ok(update_stacked_ranks(http, &mut member, &ranks, new_level).await);
+    }
+
+    let old_rank = db.get_highest_role(guild_id, old_level).await?;
+    let new_rank = db.get_highest_role(guild_id, new_level).await?;
+
+    if old_rank == new_rank {
+        return Ok(true);
+    }
+
+    let mut member = guild_id.member(http, user_id).await?;
+    Ok(update_rank_role(http, &mut member, old_rank, new_rank).await)
+}
+
+// When stacking, a member should hold every rank role at or below their level, and none above it.
+pub async fn update_stacked_ranks(http: &Http, member: &mut Member, ranks: &[Rank], level: i32) -> bool {
+    let mut success = true;
+
+    for rank in ranks {
+        if let Some(role_id) = rank.role_id {
+            let has_role = member.roles.contains(&role_id);
+
+            if rank.min_level <= level && !has_role {
+                if let Err(ex) = member.add_role(http, role_id).await {
+                    success = false;
+                    error!("Failed to add role to user: {}", ex);
+                }
+            } else if rank.min_level > level && has_role {
+                if let Err(ex) = member.remove_role(http, role_id).await {
+                    success = false;
+                    error!("Failed to remove role from user: {}", ex);
+                }
             }
         }
     }
+
+    success
 }
 
 pub async fn on_join(ctx: &Context, new_member: &Member) {
@@ -233,9 +425,18 @@ pub async fn on_join(ctx: &Context, new_member: &Member) {
     let guild_id = new_member.guild_id;
 
     let experience = db.get_xp(guild_id, member.user.id).await.unwrap();
-    let current_role = db.get_highest_role(guild_id, experience.level).await.unwrap();
-    if let Some(current_role_id) = current_role {
-        if let Err(ex) = member.add_role(&ctx.http, current_role_id).await {
+    let ranks = if db.get_role_stacking(guild_id).await.unwrap_or_default() {
+        db.get_roles(guild_id).await.unwrap()
+            .into_iter()
+            .filter(|r| r.min_level <= experience.level)
+            .filter_map(|r| r.role_id)
+            .collect::<Vec<_>>()
+    } else {
+        db.get_highest_role(guild_id, experience.level).await.unwrap().into_iter().collect::<Vec<_>>()
+    };
+
+    if !ranks.is_empty() {
+        if let Err(ex) = member.add_roles(&ctx.http, &ranks).await {
             error!("Failed to add role for server {}: {}", guild_id, ex);
             if let Err(ex2) = member.user.direct_message(&ctx.http, |m|
                 m.content("I tried to re-add your roles, but the server didn't let me. Sorry~")).await {
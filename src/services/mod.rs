@@ -1,5 +1,12 @@
 pub mod message_handler;
 pub mod bot_init;
 pub mod database;
+pub mod spam_filter;
 mod minecraft_db;
-mod gpt_db;
\ No newline at end of file
+mod gpt_db;
+mod level_up_db;
+mod season_db;
+mod history_db;
+mod autofix_db;
+mod spam_filter_db;
+mod music_db;
@@ -0,0 +1,45 @@
+use serenity::model::id::GuildId;
+use rust_decimal::{
+    Decimal,
+    prelude::FromPrimitive
+};
+
+use crate::Database;
+use crate::models::db_models::*;
+
+impl Database {
+    pub async fn get_music_config(&self, server_id: GuildId) -> Result<MusicConfig, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT default_volume FROM [Music].[Config] WHERE id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        let mut out = MusicConfig::new(server_id.0);
+
+        if let Some(item) = res {
+            out = MusicConfig {
+                id: server_id.0,
+                default_volume: item.get(0).unwrap()
+            };
+        }
+
+        Ok(out)
+    }
+
+    pub async fn update_music_config(&self, config: &MusicConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(config.id).unwrap();
+
+        conn.execute(
+            "UPDATE [Music].[Config] SET default_volume = @P2 WHERE id = @P1; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Music].[Config] (id, default_volume) VALUES (@P1, @P2);",
+            &[&server, &config.default_volume])
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::NaiveDateTime;
+use serenity::model::id::{GuildId, UserId};
+use rust_decimal::{
+    Decimal,
+    prelude::FromPrimitive
+};
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::Database;
+use crate::models::db_models::*;
+
+impl Database {
+    pub async fn get_season_config(&self, server_id: GuildId) -> Result<SeasonConfig, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT keep_percent, interval_days, next_close, champion_role FROM [Ranking].[SeasonConfig] WHERE id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        let mut out = SeasonConfig::new(server_id.0);
+
+        if let Some(item) = res {
+            let champion_role: Option<Decimal> = item.get(3);
+            out = SeasonConfig {
+                id: server_id.0,
+                keep_percent: item.get(0).unwrap(),
+                interval_days: item.get(1),
+                next_close: item.get(2),
+                champion_role: champion_role.and_then(|o| o.to_u64())
+            };
+        }
+
+        Ok(out)
+    }
+
+    pub async fn update_season_config(&self, config: &SeasonConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(config.id).unwrap();
+        let champion_role = config.champion_role.map(|o| Decimal::from_u64(o).unwrap());
+
+        conn.execute(
+            "UPDATE [Ranking].[SeasonConfig] SET keep_percent = @P2, interval_days = @P3, next_close = @P4, champion_role = @P5 WHERE id = @P1; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Ranking].[SeasonConfig] (id, keep_percent, interval_days, next_close, champion_role) VALUES (@P1, @P2, @P3, @P4, @P5);",
+            &[&server, &config.keep_percent, &config.interval_days, &config.next_close, &champion_role])
+            .await?;
+
+        Ok(())
+    }
+
+    // Archives the current standings as a new season, then keeps only keep_percent of everyone's progress.
+    pub async fn close_season(&self, server_id: GuildId, keep_percent: i32) -> Result<ClosedSeason, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SET XACT_ABORT ON; \
+            BEGIN TRANSACTION; \
+            DECLARE @season INT = (SELECT ISNULL(MAX(season), 0) + 1 FROM [Ranking].[Season] WHERE server_id = @P1); \
+            INSERT INTO [Ranking].[Season] (server_id, season, closed_at) VALUES (@P1, @season, SYSUTCDATETIME()); \
+            INSERT INTO [Ranking].[SeasonLevel] (server_id, season, [user_id], level, xp, position) \
+                SELECT server_id, @season, [user_id], level, xp, ROW_NUMBER() OVER (ORDER BY level DESC, xp DESC) FROM [Ranking].[Level] WHERE server_id = @P1; \
+            IF @P2 <= 0 \
+                DELETE FROM [Ranking].[Level] WHERE server_id = @P1; \
+            ELSE \
+                UPDATE [Ranking].[Level] SET level = level * @P2 / 100, xp = xp * @P2 / 100 WHERE server_id = @P1; \
+            COMMIT TRANSACTION; \
+            SELECT @season, \
+                (SELECT [user_id] FROM [Ranking].[SeasonLevel] WHERE server_id = @P1 AND season = @season AND position = 1), \
+                (SELECT [user_id] FROM [Ranking].[SeasonLevel] WHERE server_id = @P1 AND season = @season - 1 AND position = 1);",
+            &[&server, &keep_percent])
+            .await?
+            .into_row()
+            .await?;
+
+        let mut out = ClosedSeason {
+            season: 0,
+            champion: None,
+            previous_champion: None
+        };
+
+        if let Some(item) = res {
+            out = ClosedSeason {
+                season: item.get(0).unwrap(),
+                champion: item.get(1).and_then(|u: Decimal| u.to_u64()).map(UserId::from),
+                previous_champion: item.get(2).and_then(|u: Decimal| u.to_u64()).map(UserId::from)
+            };
+        }
+
+        Ok(out)
+    }
+
+    // Page number is zero-indexed, same as top_members.
+    pub async fn top_season_members(&self, server_id: GuildId, season: i32, page: i32) -> Result<MemberPagination, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        const ROWS_FETCHED: i64 = 10;
+        let offset = (page as i64 * ROWS_FETCHED).max(0);
+        let res = conn.query(
+            "SELECT [user_id], level, xp FROM [Ranking].[SeasonLevel] WHERE server_id = @P1 AND season = @P2 AND position > @P3 AND position <= @P3 + @P4 ORDER BY position; \
+            SELECT COUNT(1) FROM [Ranking].[SeasonLevel] WHERE server_id = @P1 AND season = @P2",
+            &[&server, &season, &offset, &ROWS_FETCHED])
+            .await?
+            .into_results()
+            .await?;
+
+        let count: i32 = res.get(1).unwrap().first().unwrap().get(0).unwrap();
+
+        let members = res.first().unwrap().iter()
+            .map(|row| {
+                let id: Decimal = row.get(0).unwrap();
+                Member {
+                    id: UserId::from(id.to_u64().unwrap()),
+                    exp: Experience {
+                        level: row.get(1).unwrap(),
+                        xp: row.get(2).unwrap()
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let pages = (count / ROWS_FETCHED as i32) + ((count % ROWS_FETCHED as i32 != 0) as i32);
+
+        Ok(MemberPagination {
+            members,
+            current_page: page,
+            last_page: pages
+        })
+    }
+
+    pub async fn get_season_standing(&self, server_id: GuildId, season: i32, user_id: UserId) -> Result<Option<SeasonStanding>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT level, xp, position FROM [Ranking].[SeasonLevel] WHERE server_id = @P1 AND season = @P2 AND [user_id] = @P3",
+            &[&server, &season, &user])
+            .await?
+            .into_row()
+            .await?;
+
+        Ok(res.map(|row| SeasonStanding {
+            exp: Experience {
+                level: row.get(0).unwrap(),
+                xp: row.get(1).unwrap()
+            },
+            rank: row.get(2).unwrap()
+        }))
+    }
+
+    pub async fn get_due_seasons(&self) -> Result<Vec<SeasonConfig>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let res = conn.simple_query(
+            "SELECT id, keep_percent, interval_days, next_close, champion_role FROM [Ranking].[SeasonConfig] WHERE interval_days IS NOT NULL AND next_close <= SYSUTCDATETIME()")
+            .await?
+            .into_first_result()
+            .await?
+            .into_iter()
+            .map(|row| {
+                let id: Decimal = row.get(0).unwrap();
+                let champion_role: Option<Decimal> = row.get(4);
+                let next_close: Option<NaiveDateTime> = row.get(3);
+                SeasonConfig {
+                    id: id.to_u64().unwrap(),
+                    keep_percent: row.get(1).unwrap(),
+                    interval_days: row.get(2),
+                    next_close,
+                    champion_role: champion_role.and_then(|o| o.to_u64())
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(res)
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use regex::Regex;
+use serenity::{
+    model::{channel::Message, id::{GuildId, UserId}},
+    prelude::TypeMapKey
+};
+use crate::models::db_models::{SpamFilterConfig, SpamReason};
+
+// How many of a user's previous messages are checked for near-duplicates.
+const RECENT_MESSAGES: usize = 5;
+// Only compare the start of long messages, since the edit distance is quadratic.
+const MAX_COMPARED_LENGTH: usize = 500;
+
+#[derive(Default)]
+pub struct Activity {
+    recent: VecDeque<String>,
+    sent: VecDeque<Instant>
+}
+
+pub struct SpamTracker;
+
+impl TypeMapKey for SpamTracker {
+    type Value = Arc<Mutex<HashMap<(GuildId, UserId), Activity>>>;
+}
+
+// Settings are needed for every message, so they're only read from the database once; `spamfilter` keeps them up to date.
+pub struct SpamFilterCache;
+
+impl TypeMapKey for SpamFilterCache {
+    type Value = Arc<Mutex<HashMap<GuildId, SpamFilterConfig>>>;
+}
+
+// Returns why the message shouldn't earn experience, if it looks like spam.
+// Every message is recorded, so spam still counts towards duplicates and bursts.
+pub fn check_message(tracker: &Mutex<HashMap<(GuildId, UserId), Activity>>, config: &SpamFilterConfig, guild_id: GuildId, msg: &Message) -> Option<SpamReason> {
+    let now = Instant::now();
+    let window = Duration::from_millis(config.burst_window.max(0) as u64);
+    let normalized = normalize(&msg.content);
+
+    // Only the bookkeeping happens under the lock; comparing messages is slow enough to hold up everyone else.
+    let (sent, recent) = {
+        let mut users = tracker.lock().unwrap();
+
+        // Don't hang on to people who stopped talking.
+        if users.len() > 10000 {
+            users.retain(|_, a| a.sent.back().map(|t| now.duration_since(*t) < Duration::from_secs(600)).unwrap_or(false));
+        }
+
+        let activity = users.entry((guild_id, msg.author.id)).or_default();
+
+        while activity.sent.front().map(|t| now.duration_since(*t) > window).unwrap_or(false) {
+            activity.sent.pop_front();
+        }
+        activity.sent.push_back(now);
+
+        let recent = activity.recent.iter().cloned().collect::<Vec<_>>();
+
+        if !normalized.is_empty() {
+            activity.recent.push_back(normalized.clone());
+            if activity.recent.len() > RECENT_MESSAGES {
+                activity.recent.pop_front();
+            }
+        }
+
+        (activity.sent.len(), recent)
+    };
+
+    classify(config, msg, &normalized, sent, &recent)
+}
+
+fn classify(config: &SpamFilterConfig, msg: &Message, normalized: &str, sent: usize, recent: &[String]) -> Option<SpamReason> {
+    if config.burst_limit > 0 && sent > config.burst_limit as usize {
+        return Some(SpamReason::Burst);
+    }
+
+    let has_attachments = !msg.attachments.is_empty();
+    let has_stickers = !msg.sticker_items.is_empty();
+
+    if config.block_sticker_only && has_stickers && !has_attachments && msg.content.trim().is_empty() {
+        return Some(SpamReason::StickerOnly);
+    }
+
+    if config.block_emoji_only && !has_attachments && is_emoji_only(&msg.content) {
+        return Some(SpamReason::EmojiOnly);
+    }
+
+    // Attachments and stickers speak for themselves, so short captions are fine.
+    if !has_attachments && !has_stickers && (msg.content.trim().chars().count() as i32) < config.min_length {
+        return Some(SpamReason::TooShort);
+    }
+
+    if config.duplicate_threshold > 0 && !normalized.is_empty() {
+        let threshold = config.duplicate_threshold as f32 / 100.0;
+        if recent.iter().any(|previous| similarity(previous, normalized) >= threshold) {
+            return Some(SpamReason::Duplicate);
+        }
+    }
+
+    None
+}
+
+// Lowercase and strip everything but letters and digits, so "hello!!" and "Hello" are the same message.
+fn normalize(content: &str) -> String {
+    content.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .take(MAX_COMPARED_LENGTH)
+        .collect()
+}
+
+fn is_emoji_only(content: &str) -> bool {
+    static CUSTOM_EMOTE: OnceLock<Regex> = OnceLock::new();
+    let regex = CUSTOM_EMOTE.get_or_init(|| Regex::new(r"<a?:\w+:\d+>").unwrap());
+    let stripped = regex.replace_all(content, "");
+
+    // Custom emotes were removed, so nothing left means it was all emotes.
+    if stripped.trim().is_empty() {
+        return !content.trim().is_empty();
+    }
+
+    stripped.chars().all(|c| c.is_whitespace() || is_emoji_char(c))
+}
+
+fn is_emoji_char(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF | // Emoticons, symbols, pictographs, flags
+        0x2600..=0x27BF | // Miscellaneous symbols and dingbats
+        0x2B00..=0x2BFF | // Arrows and stars
+        0xFE00..=0xFE0F | // Variation selectors
+        0x200D | // Zero width joiner
+        0x20E3 | // Combining keycap
+        0xE0020..=0xE007F // Tags, for subdivision flags
+    )
+}
+
+// Levenshtein distance, scaled so 1.0 is identical and 0.0 is nothing in common.
+fn similarity(a: &str, b: &str) -> f32 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            current[j + 1] = (previous[j + 1] + 1).min(current[j] + 1).min(previous[j] + cost);
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f32 / a.len().max(b.len()) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_ignores_case_and_punctuation() {
+        assert_eq!(normalize("Hello, World!!"), "helloworld");
+        assert_eq!(normalize("  ...  "), "");
+        assert_eq!(normalize(&"a".repeat(MAX_COMPARED_LENGTH + 10)).len(), MAX_COMPARED_LENGTH);
+    }
+
+    #[test]
+    fn emoji_only_messages() {
+        assert!(is_emoji_only("😀"));
+        assert!(is_emoji_only("👍🏽 ❤️"));
+        assert!(is_emoji_only("<:moo:123456789> <a:dance:987654321>"));
+        assert!(is_emoji_only("<:moo:123456789> 🐮"));
+        assert!(!is_emoji_only("moo 🐮"));
+        assert!(!is_emoji_only("<:moo:123456789> moo"));
+        assert!(!is_emoji_only(""));
+        assert!(!is_emoji_only("   "));
+    }
+
+    #[test]
+    fn similarity_scales_edit_distance() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("moo", "moo"), 1.0);
+        assert_eq!(similarity("moo", ""), 0.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        assert!((similarity("hello", "hallo") - 0.8).abs() < 0.001);
+        assert_eq!(similarity("kitten", "sitting"), similarity("sitting", "kitten"));
+    }
+}
@@ -0,0 +1,110 @@
+use serenity::model::id::{GuildId, UserId};
+use rust_decimal::{
+    Decimal,
+    prelude::FromPrimitive
+};
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::Database;
+use crate::models::db_models::*;
+
+impl Database {
+    pub async fn get_spam_filter_config(&self, server_id: GuildId) -> Result<SpamFilterConfig, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT enabled, min_length, duplicate_threshold, block_emoji_only, block_sticker_only, burst_limit, burst_window FROM [Ranking].[SpamFilter] WHERE id = @P1",
+            &[&server])
+            .await?
+            .into_row()
+            .await?;
+
+        let mut out = SpamFilterConfig::new(server_id.0);
+
+        if let Some(item) = res {
+            out = SpamFilterConfig {
+                id: server_id.0,
+                enabled: item.get(0).unwrap(),
+                min_length: item.get(1).unwrap(),
+                duplicate_threshold: item.get(2).unwrap(),
+                block_emoji_only: item.get(3).unwrap(),
+                block_sticker_only: item.get(4).unwrap(),
+                burst_limit: item.get(5).unwrap(),
+                burst_window: item.get(6).unwrap()
+            };
+        }
+
+        Ok(out)
+    }
+
+    pub async fn update_spam_filter_config(&self, config: &SpamFilterConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(config.id).unwrap();
+
+        conn.execute(
+            "UPDATE [Ranking].[SpamFilter] SET enabled = @P2, min_length = @P3, duplicate_threshold = @P4, block_emoji_only = @P5, block_sticker_only = @P6, burst_limit = @P7, burst_window = @P8 WHERE id = @P1; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Ranking].[SpamFilter] (id, enabled, min_length, duplicate_threshold, block_emoji_only, block_sticker_only, burst_limit, burst_window) VALUES (@P1, @P2, @P3, @P4, @P5, @P6, @P7, @P8);",
+            &[&server, &config.enabled, &config.min_length, &config.duplicate_threshold, &config.block_emoji_only, &config.block_sticker_only, &config.burst_limit, &config.burst_window])
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_spam_strike(&self, server_id: GuildId, user_id: UserId, reason: SpamReason) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let user = Decimal::from_u64(*user_id.as_u64()).unwrap();
+        let reason = reason as u8;
+
+        conn.execute(
+            "UPDATE [Ranking].[SpamCounter] SET count = count + 1 WHERE server_id = @P1 AND [user_id] = @P2 AND reason = @P3; \
+            IF @@ROWCOUNT = 0 INSERT INTO [Ranking].[SpamCounter] (server_id, [user_id], reason, count) VALUES (@P1, @P2, @P3, 1);",
+            &[&server, &user, &reason])
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_spam_counters(&self, server_id: GuildId) -> Result<SpamCounters, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+        let res = conn.query(
+            "SELECT reason, SUM(count) FROM [Ranking].[SpamCounter] WHERE server_id = @P1 GROUP BY reason ORDER BY reason; \
+            SELECT TOP 5 [user_id], SUM(count) AS total FROM [Ranking].[SpamCounter] WHERE server_id = @P1 GROUP BY [user_id] ORDER BY total DESC",
+            &[&server])
+            .await?
+            .into_results()
+            .await?;
+
+        let reasons = res.first().unwrap().iter()
+            .filter_map(|row| {
+                let reason: u8 = row.get(0).unwrap();
+                SpamReason::try_from(reason).ok().map(|r| (r, row.get(1).unwrap()))
+            })
+            .collect::<Vec<_>>();
+
+        let offenders = res.get(1).unwrap().iter()
+            .map(|row| {
+                let id: Decimal = row.get(0).unwrap();
+                (UserId::from(id.to_u64().unwrap()), row.get(1).unwrap())
+            })
+            .collect::<Vec<_>>();
+
+        Ok(SpamCounters {
+            reasons,
+            offenders
+        })
+    }
+
+    pub async fn reset_spam_counters(&self, server_id: GuildId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let server = Decimal::from_u64(*server_id.as_u64()).unwrap();
+
+        conn.execute(
+            "DELETE FROM [Ranking].[SpamCounter] WHERE server_id = @P1",
+            &[&server])
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use std::io::Cursor;
+use chrono::NaiveDate;
+use image::{ImageOutputFormat, RgbImage};
+use plotters::prelude::*;
+use crate::Error;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 400;
+
+// Renders a simple line chart of values over days, encoded as a PNG.
+// The chart has no text, since drawing any needs system fonts; callers put the title and range in the message instead.
+pub fn line_chart_png(points: &[(NaiveDate, i64)]) -> Result<Vec<u8>, Error> {
+    if points.is_empty() {
+        return Err("There's nothing to chart".into());
+    }
+
+    let mut buffer = vec![0u8; (WIDTH * HEIGHT * 3) as usize];
+
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)?;
+
+        let start = points[0].0;
+        // A single day would make an empty range, so always show at least one day after.
+        let end = points[points.len() - 1].0.max(start + chrono::Duration::days(1));
+        let min = points.iter().map(|p| p.1).min().unwrap();
+        let max = points.iter().map(|p| p.1).max().unwrap().max(min + 1);
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(15)
+            .build_cartesian_2d(RangedDate::from(start..end), min..max)?;
+
+        chart.configure_mesh()
+            .x_labels(8)
+            .draw()?;
+
+        chart.draw_series(LineSeries::new(points.iter().copied(), RGBColor(88, 101, 242).stroke_width(3)))?;
+        chart.draw_series(points.iter().map(|p| Circle::new(*p, 3, RGBColor(88, 101, 242).filled())))?;
+
+        root.present()?;
+    }
+
+    let image = RgbImage::from_raw(WIDTH, HEIGHT, buffer).ok_or("Chart buffer was the wrong size")?;
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageOutputFormat::Png)?;
+
+    Ok(png.into_inner())
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::InteractionResponseType;
+use tracing::error;
+use crate::{CowContext, Error};
+
+// Asks the command author to confirm a destructive action with a pair of buttons.
+// Anything other than pressing "Confirm" within 30 seconds counts as a no.
+pub async fn confirm(ctx: &CowContext<'_>, prompt: &str) -> Result<bool, Error> {
+    let reply = ctx.send(|m| {
+        m.content(prompt)
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| b
+                        .style(ButtonStyle::Danger)
+                        .label("Confirm")
+                        .custom_id("confirm:yes")
+                    )
+                    .create_button(|b| b
+                        .style(ButtonStyle::Secondary)
+                        .label("Cancel")
+                        .custom_id("confirm:no")
+                    )
+                })
+            })
+    }).await?;
+
+    let message = reply.message().await?;
+    let interaction = message
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(30))
+        .await;
+
+    let confirmed = interaction.as_ref().map(|i| i.data.custom_id == "confirm:yes").unwrap_or(false);
+
+    if let Some(interaction) = interaction {
+        if let Err(ex) = interaction.create_interaction_response(ctx.serenity_context(), |r| r.kind(InteractionResponseType::DeferredUpdateMessage)).await {
+            error!("Failed to acknowledge confirmation: {}", ex);
+        }
+    }
+
+    reply.edit(*ctx, |m| {
+        m.content(format!("{prompt}\n{}", if confirmed { "Confirmed." } else { "Cancelled." }))
+            .components(|c| c)
+    }).await?;
+
+    Ok(confirmed)
+}
@@ -1,24 +1,76 @@
+// A number without a unit is rejected rather than guessed at, so "10" isn't silently read as nothing.
 pub fn to_ms<S: Into<String>>(s: S) -> Option<i32> {
-    let mut ms = 0;
-    let mut digits = 0;
+    let mut ms: u64 = 0;
+    let mut digits: Option<u64> = None;
     for c in s.into().chars() {
         if c.is_ascii_digit() {
-            digits *= 10;
-            digits += c.to_digit(10).unwrap();
+            digits = Some(digits.unwrap_or(0).checked_mul(10)?.checked_add(c.to_digit(10).unwrap() as u64)?);
         } else {
-            ms += match c {
-                's' => digits * 1000,
-                'm' => digits * 60 * 1000,
-                'h' => digits * 60 * 60 * 1000,
-                'd' => digits * 24 * 60 * 60 * 1000,
-                _ => { return None; }
-            };
-
-            digits = 0;
+            ms = ms.checked_add(digits.take()?.checked_mul(unit_ms(c)?)?)?;
         }
     }
 
-    Some(ms as i32)
+    if digits.is_some() {
+        return None;
+    }
+
+    i32::try_from(ms).ok()
+}
+
+// Accepts either 1:30 / 1:02:30 or 1m30s, where a bare number at the end counts as seconds.
+pub fn timestamp_to_ms(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+
+    if s.contains(':') {
+        let parts = s.split(':').collect::<Vec<_>>();
+        if parts.len() > 3 {
+            return None;
+        }
+
+        let mut secs: u64 = 0;
+        for (i, part) in parts.iter().enumerate() {
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+
+            let value = part.parse::<u64>().ok()?;
+            if i > 0 && value >= 60 {
+                return None;
+            }
+
+            secs = secs.checked_mul(60)?.checked_add(value)?;
+        }
+
+        return secs.checked_mul(1000);
+    }
+
+    let mut ms: u64 = 0;
+    let mut digits: Option<u64> = None;
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits = Some(digits.unwrap_or(0).checked_mul(10)?.checked_add(c.to_digit(10).unwrap() as u64)?);
+        } else {
+            ms = ms.checked_add(digits.take()?.checked_mul(unit_ms(c)?)?)?;
+        }
+    }
+
+    match digits {
+        Some(secs) => ms.checked_add(secs.checked_mul(1000)?),
+        None => Some(ms)
+    }
+}
+
+fn unit_ms(c: char) -> Option<u64> {
+    match c {
+        's' => Some(1000),
+        'm' => Some(60 * 1000),
+        'h' => Some(60 * 60 * 1000),
+        'd' => Some(24 * 60 * 60 * 1000),
+        _ => None
+    }
 }
 
 pub fn from_ms(ms: u64) -> String {
@@ -39,4 +91,68 @@ pub fn from_ms(ms: u64) -> String {
     } else {
         format!("{s}s")
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_ms_adds_up_units() {
+        assert_eq!(to_ms("10s"), Some(10_000));
+        assert_eq!(to_ms("1m30s"), Some(90_000));
+        assert_eq!(to_ms("1d2h3m4s"), Some(93_784_000));
+        assert_eq!(to_ms(""), Some(0));
+    }
+
+    #[test]
+    fn to_ms_rejects_numbers_without_units() {
+        assert_eq!(to_ms("10"), None);
+        assert_eq!(to_ms("1m30"), None);
+        assert_eq!(to_ms("m"), None);
+        assert_eq!(to_ms("10x"), None);
+    }
+
+    #[test]
+    fn to_ms_rejects_overflow() {
+        assert_eq!(to_ms("24d"), Some(2_073_600_000));
+        assert_eq!(to_ms("25d"), None);
+        assert_eq!(to_ms("99999999999999999999s"), None);
+    }
+
+    #[test]
+    fn timestamp_to_ms_reads_clock_times() {
+        assert_eq!(timestamp_to_ms("1:30"), Some(90_000));
+        assert_eq!(timestamp_to_ms("1:02:30"), Some(3_750_000));
+        assert_eq!(timestamp_to_ms("0:05"), Some(5_000));
+        assert_eq!(timestamp_to_ms(" 90:00 "), Some(5_400_000));
+    }
+
+    #[test]
+    fn timestamp_to_ms_reads_units_and_bare_seconds() {
+        assert_eq!(timestamp_to_ms("1m30s"), Some(90_000));
+        assert_eq!(timestamp_to_ms("1m30"), Some(90_000));
+        assert_eq!(timestamp_to_ms("2h"), Some(7_200_000));
+        assert_eq!(timestamp_to_ms("45"), Some(45_000));
+    }
+
+    #[test]
+    fn timestamp_to_ms_rejects_invalid_input() {
+        assert_eq!(timestamp_to_ms(""), None);
+        assert_eq!(timestamp_to_ms("1:60"), None);
+        assert_eq!(timestamp_to_ms("1:2:3:4"), None);
+        assert_eq!(timestamp_to_ms("1::30"), None);
+        assert_eq!(timestamp_to_ms(":30"), None);
+        assert_eq!(timestamp_to_ms("1:3a"), None);
+        assert_eq!(timestamp_to_ms("m"), None);
+        assert_eq!(timestamp_to_ms("10x"), None);
+    }
+
+    #[test]
+    fn timestamp_to_ms_rejects_overflow() {
+        assert_eq!(timestamp_to_ms("99999999999999999999"), None);
+        assert_eq!(timestamp_to_ms("18446744073709551615:00"), None);
+        assert_eq!(timestamp_to_ms("18446744073709551615"), None);
+        assert_eq!(timestamp_to_ms("9999999999999999d"), None);
+    }
+}
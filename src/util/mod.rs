@@ -1,4 +1,9 @@
 mod duration;
+mod confirm;
+mod chart;
 
 pub use duration::to_ms;
-pub use duration::from_ms;
\ No newline at end of file
+pub use duration::timestamp_to_ms;
+pub use duration::from_ms;
+pub use confirm::confirm;
+pub use chart::line_chart_png;